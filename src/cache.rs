@@ -0,0 +1,525 @@
+//! Persistent caches of per-file results
+//!
+//! Extracting a timestamp may mean parsing EXIF or container metadata, or
+//! spawning FFprobe, and hashing reads the whole file. Incremental runs
+//! extract the time of every source file to compare it with the watermark,
+//! and Supplement runs hash the output library to find files that are
+//! already there. Both results are cached per path along with the stamp of
+//! the file they were computed from: its metadata hash (size + mtime) and
+//! inode. Timestamps are also stamped with the XMP sidecars and Takeout JSON
+//! files they may be read from, so adding or editing one of those counts as
+//! a change. Unchanged files are not read again, neither later in the same
+//! run nor in later runs.
+//!
+//! A cache is only valid for the settings it was built with, including
+//! whether FFprobe is installed, and is discarded as a whole when those
+//! change.
+
+use crate::config::{Config, TimeSourceKind};
+use crate::error::{Error, Result};
+use crate::hash::{compute_file_hash, hash_metadata};
+use crate::os;
+use crate::time::video::is_ffprobe_available;
+use crate::time::{ExtractedTime, TimePrecision, TimeSource, extract_time};
+use crate::time::{export, xmp};
+use chrono::{Duration, FixedOffset, NaiveDateTime};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use tracing::{debug, info, trace, warn};
+use xxhash_rust::xxh3::xxh3_64;
+
+/// Version of a file a cached value was computed from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct FileStamp {
+    /// Hash of size and modification time
+    metadata_hash: u64,
+    /// Inode number, 0 where the platform has none
+    inode: u64,
+    /// Hash of the paths and stamps of the companion files that exist
+    #[serde(default)]
+    companions: u64,
+}
+
+impl FileStamp {
+    /// Stamp of a file and the companion files its value may be read from
+    fn of(path: &Path, companions: &[PathBuf]) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        let mut bytes = Vec::new();
+        for companion in companions {
+            if let Some(stamp) = Self::of(companion, &[]) {
+                bytes.extend_from_slice(companion.as_os_str().as_encoded_bytes());
+                bytes.extend_from_slice(&stamp.metadata_hash.to_le_bytes());
+                bytes.extend_from_slice(&stamp.inode.to_le_bytes());
+            }
+        }
+        Some(Self {
+            metadata_hash: hash_metadata(&metadata),
+            inode: os::file_id(&metadata),
+            companions: xxh3_64(&bytes),
+        })
+    }
+}
+
+/// Cached value of one file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry<V> {
+    stamp: FileStamp,
+    value: V,
+}
+
+/// On-disk form of a cache, owning its entries when loaded
+#[derive(Serialize, Deserialize)]
+struct CacheFile<E> {
+    /// Version for cache file format compatibility
+    version: u32,
+    /// Hash of the settings the entries were computed with
+    settings_hash: u64,
+    entries: E,
+}
+
+/// Values by source path, shared between threads
+#[derive(Debug)]
+struct FileCache<V> {
+    /// Name used in log messages
+    name: &'static str,
+    settings_hash: u64,
+    entries: Mutex<HashMap<PathBuf, Entry<V>>>,
+    /// Whether entries were added since loading
+    dirty: AtomicBool,
+    /// Lookups answered from the cache
+    hits: AtomicUsize,
+}
+
+impl<V: Clone + Serialize + DeserializeOwned> FileCache<V> {
+    /// Current cache file format version
+    const VERSION: u32 = 2;
+
+    fn new(name: &'static str, settings_hash: u64) -> Self {
+        Self {
+            name,
+            settings_hash,
+            entries: Mutex::new(HashMap::new()),
+            dirty: AtomicBool::new(false),
+            hits: AtomicUsize::new(0),
+        }
+    }
+
+    /// Load a cache, starting empty if the file is missing, unreadable or outdated
+    fn load(name: &'static str, path: &Path, settings_hash: u64) -> Self {
+        let cache = Self::new(name, settings_hash);
+        if !path.exists() {
+            debug!(cache = name, ?path, "Cache does not exist, starting empty");
+            return cache;
+        }
+
+        let file: CacheFile<HashMap<PathBuf, Entry<V>>> = match File::open(path)
+            .map_err(Error::from)
+            .and_then(|file| Ok(serde_json::from_reader(BufReader::new(file))?))
+        {
+            Ok(file) => file,
+            Err(e) => {
+                warn!(cache = name, ?path, error = %e, "Failed to read cache, starting empty");
+                return cache;
+            }
+        };
+
+        if file.version != Self::VERSION || file.settings_hash != settings_hash {
+            info!(
+                cache = name,
+                "Cache was built with other settings, starting empty"
+            );
+            return cache;
+        }
+
+        info!(cache = name, entries = file.entries.len(), "Loaded cache");
+        Self {
+            entries: Mutex::new(file.entries),
+            ..cache
+        }
+    }
+
+    /// Save the cache, dropping entries of files that no longer exist
+    fn save(&self, path: &Path) -> Result<()> {
+        let mut entries = self.entries.lock().unwrap();
+        let count = entries.len();
+        entries.retain(|path, _| path.exists());
+        if !self.dirty.load(Ordering::Relaxed) && entries.len() == count {
+            debug!(cache = self.name, "Cache unchanged, not saving");
+            return Ok(());
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // Write to a temporary file first, then rename for atomicity
+        let temp_path = path.with_extension("tmp");
+        let file = CacheFile {
+            version: Self::VERSION,
+            settings_hash: self.settings_hash,
+            entries: &*entries,
+        };
+        serde_json::to_writer(BufWriter::new(File::create(&temp_path)?), &file)?;
+        fs::rename(&temp_path, path)?;
+
+        info!(
+            cache = self.name,
+            entries = entries.len(),
+            hits = self.hits.load(Ordering::Relaxed),
+            "Saved cache"
+        );
+        self.dirty.store(false, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Cached value of an unchanged file, or compute and cache it
+    ///
+    /// The file counts as changed when any of `companions` is added, removed
+    /// or modified. Errors are not cached, so files without a result are
+    /// retried on every lookup.
+    fn get_or_compute(
+        &self,
+        path: &Path,
+        companions: &[PathBuf],
+        compute: impl FnOnce() -> Result<V>,
+    ) -> Result<V> {
+        let Some(stamp) = FileStamp::of(path, companions) else {
+            return compute();
+        };
+
+        if let Some(entry) = self.entries.lock().unwrap().get(path)
+            && entry.stamp == stamp
+        {
+            trace!(cache = self.name, ?path, "Using cached value");
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(entry.value.clone());
+        }
+
+        let value = compute()?;
+        self.entries.lock().unwrap().insert(
+            path.to_path_buf(),
+            Entry {
+                stamp,
+                value: value.clone(),
+            },
+        );
+        self.dirty.store(true, Ordering::Relaxed);
+        Ok(value)
+    }
+
+    fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+}
+
+/// Cached extraction result of one file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedTime {
+    timestamp: NaiveDateTime,
+    source: TimeSource,
+    precision: TimePrecision,
+    /// UTC offset in seconds
+    offset: Option<i32>,
+    /// Applied camera clock correction in milliseconds
+    clock_correction: Option<i64>,
+}
+
+impl From<&ExtractedTime> for CachedTime {
+    fn from(time: &ExtractedTime) -> Self {
+        Self {
+            timestamp: time.timestamp,
+            source: time.source,
+            precision: time.precision,
+            offset: time.offset.map(|o| o.local_minus_utc()),
+            clock_correction: time.clock_correction.map(|c| c.num_milliseconds()),
+        }
+    }
+}
+
+impl From<CachedTime> for ExtractedTime {
+    fn from(time: CachedTime) -> Self {
+        Self {
+            timestamp: time.timestamp,
+            source: time.source,
+            precision: time.precision,
+            offset: time.offset.and_then(FixedOffset::east_opt),
+            clock_correction: time.clock_correction.map(Duration::milliseconds),
+        }
+    }
+}
+
+/// Extracted timestamps by source path
+#[derive(Debug)]
+pub struct MetadataCache(FileCache<CachedTime>);
+
+impl MetadataCache {
+    const NAME: &str = "metadata";
+
+    /// Create an empty cache for the time settings of `config`
+    pub fn new(config: &Config) -> Self {
+        Self(FileCache::new(Self::NAME, time_settings_hash(config)))
+    }
+
+    /// Load the cache from file
+    ///
+    /// A missing, unreadable or outdated cache file gives an empty cache.
+    pub fn load(path: &Path, config: &Config) -> Self {
+        Self(FileCache::load(
+            Self::NAME,
+            path,
+            time_settings_hash(config),
+        ))
+    }
+
+    /// Save the cache to file, dropping entries of files that no longer exist
+    pub fn save(&self, path: &Path) -> Result<()> {
+        self.0.save(path)
+    }
+
+    /// Extract the time of a file, reusing the cached result if the file is unchanged
+    pub fn extract_time(&self, path: &Path, config: &Config) -> Result<ExtractedTime> {
+        let mut companions = Vec::new();
+        if config.time_sources.contains(&TimeSourceKind::Xmp) {
+            companions.extend(xmp::find_sidecars(path));
+        }
+        if config.time_sources.contains(&TimeSourceKind::Export) {
+            companions.extend(export::metadata_candidates(path));
+        }
+        self.0
+            .get_or_compute(path, &companions, || {
+                extract_time(path, config).map(|time| CachedTime::from(&time))
+            })
+            .map(ExtractedTime::from)
+    }
+
+    /// Number of cached files
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Content hashes by path, for source files and the output library alike
+#[derive(Debug)]
+pub struct HashCache {
+    cache: FileCache<u128>,
+    large_file_threshold: u64,
+}
+
+impl HashCache {
+    const NAME: &str = "hash";
+
+    /// Create an empty cache for the hash settings of `config`
+    pub fn new(config: &Config) -> Self {
+        Self {
+            cache: FileCache::new(Self::NAME, config.large_file_threshold),
+            large_file_threshold: config.large_file_threshold,
+        }
+    }
+
+    /// Load the cache from file
+    ///
+    /// A missing, unreadable or outdated cache file gives an empty cache.
+    pub fn load(path: &Path, config: &Config) -> Self {
+        Self {
+            cache: FileCache::load(Self::NAME, path, config.large_file_threshold),
+            large_file_threshold: config.large_file_threshold,
+        }
+    }
+
+    /// Save the cache to file, dropping entries of files that no longer exist
+    pub fn save(&self, path: &Path) -> Result<()> {
+        self.cache.save(path)
+    }
+
+    /// Hash a file, reusing the cached hash if the file is unchanged
+    pub fn compute_file_hash(&self, path: &Path) -> Result<u128> {
+        self.cache.get_or_compute(path, &[], || {
+            compute_file_hash(path, self.large_file_threshold)
+        })
+    }
+
+    /// Number of cached files
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    /// Whether the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Hash of every setting that affects which time is extracted for a file
+///
+/// Whether FFprobe is installed is part of it: without FFprobe the chain
+/// falls through to later sources, and those results must not outlive the
+/// missing tool.
+fn time_settings_hash(config: &Config) -> u64 {
+    let settings = serde_json::to_vec(&(
+        &config.input_dirs,
+        &config.timezone,
+        &config.timezone_schedule,
+        &config.time_sources,
+        &config.file_time,
+        &config.time_sources_by_type,
+        &config.time_sources_by_dir,
+        &config.filename_patterns,
+        &config.plausibility,
+        (
+            &config.conflict_resolution,
+            &config.preferred_source,
+            &config.conflict_threshold_hours,
+        ),
+        &config.camera_clock_offsets,
+        (
+            &config.image_extensions,
+            &config.video_extensions,
+            &config.raw_extensions,
+        ),
+        is_ffprobe_available(),
+    ))
+    .unwrap_or_default();
+    xxh3_64(&settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ConflictResolution, TimeSourceKind};
+
+    #[test]
+    fn test_unchanged_files_use_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("IMG_20190501_100000.jpg");
+        fs::write(&path, b"not a jpeg").unwrap();
+        let config = Config::default();
+        let cache = MetadataCache::new(&config);
+
+        let time = cache.extract_time(&path, &config).unwrap();
+        assert_eq!(time.source, TimeSource::Filename);
+        assert_eq!(cache.len(), 1);
+
+        // A cached entry is returned as long as the file is unchanged
+        let mut value = CachedTime::from(&time);
+        value.source = TimeSource::Exif;
+        let stamp = FileStamp::of(&path, &[]).unwrap();
+        let entry = Entry { stamp, value };
+        cache.0.entries.lock().unwrap().insert(path.clone(), entry);
+        let cached = cache.extract_time(&path, &config).unwrap();
+        assert_eq!(cached.source, TimeSource::Exif);
+        assert_eq!(cached.timestamp, time.timestamp);
+
+        fs::write(&path, b"a different size").unwrap();
+        let time = cache.extract_time(&path, &config).unwrap();
+        assert_eq!(time.source, TimeSource::Filename);
+    }
+
+    #[test]
+    fn test_companion_files_invalidate_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("IMG_20190501_100000.jpg");
+        fs::write(&path, b"not a jpeg").unwrap();
+        let config = Config::default();
+        let cache = MetadataCache::new(&config);
+        assert_eq!(
+            cache.extract_time(&path, &config).unwrap().source,
+            TimeSource::Filename
+        );
+
+        // Adding a Takeout JSON or XMP sidecar doesn't touch the media file
+        fs::write(
+            dir.path().join("IMG_20190501_100000.jpg.json"),
+            r#"{ "photoTakenTime": { "timestamp": "1311501600" } }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            cache.extract_time(&path, &config).unwrap().source,
+            TimeSource::ExportMetadata
+        );
+
+        let sidecar = dir.path().join("IMG_20190501_100000.xmp");
+        fs::write(
+            &sidecar,
+            r#"<rdf:Description exif:DateTimeOriginal="2011-07-24T10:15:30"/>"#,
+        )
+        .unwrap();
+        let time = cache.extract_time(&path, &config).unwrap();
+        assert_eq!(time.source, TimeSource::XmpSidecar);
+        assert_eq!(time.timestamp.to_string(), "2011-07-24 10:15:30");
+
+        fs::write(
+            &sidecar,
+            r#"<rdf:Description exif:DateTimeOriginal="2012-08-25T11:16:31"/>"#,
+        )
+        .unwrap();
+        let time = cache.extract_time(&path, &config).unwrap();
+        assert_eq!(time.timestamp.to_string(), "2012-08-25 11:16:31");
+        assert_eq!(cache.0.hits.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("IMG_20190501_100000.jpg");
+        fs::write(&path, b"not a jpeg").unwrap();
+        let cache_path = dir.path().join("cache.json");
+        let mut config = Config::default();
+
+        let cache = MetadataCache::new(&config);
+        cache.extract_time(&path, &config).unwrap();
+        cache.save(&cache_path).unwrap();
+
+        let loaded = MetadataCache::load(&cache_path, &config);
+        assert_eq!(loaded.len(), 1);
+        loaded.extract_time(&path, &config).unwrap();
+        assert_eq!(loaded.0.hits.load(Ordering::Relaxed), 1);
+
+        // Other time settings invalidate the whole cache
+        config.conflict_resolution = ConflictResolution::Majority;
+        assert!(MetadataCache::load(&cache_path, &config).is_empty());
+        config.conflict_resolution = ConflictResolution::First;
+        config.raw_extensions.push("jpg".into());
+        assert!(MetadataCache::load(&cache_path, &config).is_empty());
+        config.time_sources = vec![TimeSourceKind::Mtime];
+        assert!(MetadataCache::load(&cache_path, &config).is_empty());
+
+        fs::write(&cache_path, b"garbage").unwrap();
+        assert!(MetadataCache::load(&cache_path, &config).is_empty());
+    }
+
+    #[test]
+    fn test_hash_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.jpg");
+        fs::write(&path, b"content").unwrap();
+        let cache_path = dir.path().join("hashes.json");
+        let mut config = Config::default();
+
+        let cache = HashCache::new(&config);
+        let hash = cache.compute_file_hash(&path).unwrap();
+        assert_eq!(
+            hash,
+            compute_file_hash(&path, config.large_file_threshold).unwrap()
+        );
+        cache.save(&cache_path).unwrap();
+
+        let loaded = HashCache::load(&cache_path, &config);
+        assert_eq!(loaded.compute_file_hash(&path).unwrap(), hash);
+        assert_eq!(loaded.cache.hits.load(Ordering::Relaxed), 1);
+
+        // Sampled and full hashes differ, so a new threshold starts over
+        config.large_file_threshold = 1;
+        assert!(HashCache::load(&cache_path, &config).is_empty());
+    }
+}
@@ -1,6 +1,10 @@
 //! CLI argument parsing with clap
 
-use crate::config::{ClassificationRule, Config, FileOperation, MonthFormat, ProcessingMode};
+use crate::config::{
+    ClassificationRule, Config, ConflictResolution, FileOperation, FileTimeKind, MonthFormat,
+    ProcessingMode, TimeSourceKind,
+};
+use chrono_tz::Tz;
 use clap::Parser;
 use std::path::PathBuf;
 
@@ -48,6 +52,10 @@ pub struct Cli {
     #[arg(long)]
     pub classify_by_type: bool,
 
+    /// Classify by source app (adds WeChat/Telegram/... subdirectory)
+    #[arg(long)]
+    pub classify_by_app: bool,
+
     /// File operation mode
     #[arg(short = 'O', long, value_enum)]
     pub operation: Option<FileOperation>,
@@ -56,6 +64,14 @@ pub struct Cli {
     #[arg(long)]
     pub no_deduplicate: bool,
 
+    /// Skip images that look like a larger image (re-saved or resized copies)
+    #[arg(long)]
+    pub near_duplicates: bool,
+
+    /// Maximum differing bits of 64 for near-duplicate images
+    #[arg(long, value_name = "BITS")]
+    pub near_duplicate_threshold: Option<u32>,
+
     /// State file path for tracking processed files
     #[arg(long)]
     pub state_file: Option<PathBuf>,
@@ -68,6 +84,38 @@ pub struct Cli {
     #[arg(long)]
     pub large_file_mb: Option<u64>,
 
+    /// IANA timezone for UTC-based timestamps (e.g. Europe/Berlin)
+    #[arg(long, value_name = "ZONE")]
+    pub timezone: Option<Tz>,
+
+    /// Time sources to try, in order (comma-separated)
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub time_sources: Option<Vec<TimeSourceKind>>,
+
+    /// File system time used by the mtime source
+    #[arg(long, value_enum)]
+    pub file_time: Option<FileTimeKind>,
+
+    /// Run every time source and write a report of conflicting timestamps
+    #[arg(long)]
+    pub audit: bool,
+
+    /// Audit report path (default: in output directory)
+    #[arg(long)]
+    pub audit_report: Option<PathBuf>,
+
+    /// Which timestamp to use when time sources conflict
+    #[arg(long, value_enum)]
+    pub conflict_resolution: Option<ConflictResolution>,
+
+    /// Time source used by `--conflict-resolution prefer`
+    #[arg(long, value_enum)]
+    pub prefer_source: Option<TimeSourceKind>,
+
+    /// Interpolate times of undated files from camera sequence numbers
+    #[arg(long)]
+    pub interpolate: bool,
+
     /// Dry run mode - show what would be done without doing it
     #[arg(short = 'n', long)]
     pub dry_run: bool,
@@ -113,12 +161,21 @@ impl Cli {
         if self.classify_by_type {
             config.classify_by_type = true;
         }
+        if self.classify_by_app {
+            config.classify_by_app = true;
+        }
         if let Some(operation) = self.operation {
             config.operation = operation;
         }
         if self.no_deduplicate {
             config.deduplicate = false;
         }
+        if self.near_duplicates {
+            config.near_duplicates = true;
+        }
+        if let Some(threshold) = self.near_duplicate_threshold {
+            config.near_duplicate_threshold = threshold;
+        }
         if let Some(ref state_file) = self.state_file {
             config.state_file = Some(state_file.clone());
         }
@@ -128,6 +185,30 @@ impl Cli {
         if let Some(large_file_mb) = self.large_file_mb {
             config.large_file_threshold = large_file_mb * 1024 * 1024;
         }
+        if let Some(timezone) = self.timezone {
+            config.timezone = Some(timezone);
+        }
+        if let Some(ref time_sources) = self.time_sources {
+            config.time_sources = time_sources.clone();
+        }
+        if let Some(file_time) = self.file_time {
+            config.file_time = file_time;
+        }
+        if self.audit {
+            config.audit = true;
+        }
+        if let Some(ref audit_report) = self.audit_report {
+            config.audit_report = Some(audit_report.clone());
+        }
+        if let Some(conflict_resolution) = self.conflict_resolution {
+            config.conflict_resolution = conflict_resolution;
+        }
+        if let Some(prefer_source) = self.prefer_source {
+            config.preferred_source = Some(prefer_source);
+        }
+        if self.interpolate {
+            config.interpolate_sequences = true;
+        }
         if self.dry_run {
             config.dry_run = true;
         }
@@ -160,10 +241,15 @@ impl Cli {
             config.month_format = month_format;
         }
         config.classify_by_type = self.classify_by_type;
+        config.classify_by_app = self.classify_by_app;
         if let Some(operation) = self.operation {
             config.operation = operation;
         }
         config.deduplicate = !self.no_deduplicate;
+        config.near_duplicates = self.near_duplicates;
+        if let Some(threshold) = self.near_duplicate_threshold {
+            config.near_duplicate_threshold = threshold;
+        }
         config.state_file = self.state_file.clone();
         if let Some(threads) = self.threads {
             config.threads = threads;
@@ -171,6 +257,20 @@ impl Cli {
         if let Some(large_file_mb) = self.large_file_mb {
             config.large_file_threshold = large_file_mb * 1024 * 1024;
         }
+        config.timezone = self.timezone;
+        if let Some(ref time_sources) = self.time_sources {
+            config.time_sources = time_sources.clone();
+        }
+        if let Some(file_time) = self.file_time {
+            config.file_time = file_time;
+        }
+        config.audit = self.audit;
+        config.audit_report = self.audit_report.clone();
+        if let Some(conflict_resolution) = self.conflict_resolution {
+            config.conflict_resolution = conflict_resolution;
+        }
+        config.preferred_source = self.prefer_source;
+        config.interpolate_sequences = self.interpolate;
         config.dry_run = self.dry_run;
         config.verbose = self.verbose;
 
@@ -1,8 +1,15 @@
 //! Configuration types for the gallery sorter
 
-use serde::{Deserialize, Serialize};
+use crate::time::exif::CameraInfo;
+use crate::time::timezone::ZoneRules;
+use chrono::format::{Item, StrftimeItems};
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, Timelike};
+use chrono_tz::Tz;
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
 /// 可枚举选项，用于统一选择逻辑。
 pub trait EnumOption: Clone + Copy + Default + PartialEq + 'static {
@@ -81,6 +88,251 @@ impl FileType {
     }
 }
 
+/// Time source that can be listed in a `time_sources` chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeSourceKind {
+    /// EXIF metadata (images only)
+    Exif,
+    /// XMP sidecar next to the file (`.xmp`)
+    Xmp,
+    /// Native MP4/MOV, AVI, Matroska and AVCHD parsers (videos only)
+    Container,
+    /// Video metadata via FFprobe (videos only)
+    Ffprobe,
+    /// Google Takeout JSON metadata next to the file
+    Export,
+    /// Timestamp parsed from the filename
+    Filename,
+    /// Date parsed from the names of enclosing directories
+    Directory,
+    /// File system time, modification or birth time (see `file_time`)
+    Mtime,
+}
+
+/// File system time used by the `mtime` time source
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum FileTimeKind {
+    /// Last modification time
+    #[default]
+    Mtime,
+    /// Birth (creation) time, where the file system records it
+    Btime,
+    /// The older of the two
+    Oldest,
+}
+
+impl TimeSourceKind {
+    /// Name used in configuration files
+    pub fn name(&self) -> &'static str {
+        match self {
+            TimeSourceKind::Exif => "exif",
+            TimeSourceKind::Xmp => "xmp",
+            TimeSourceKind::Container => "container",
+            TimeSourceKind::Ffprobe => "ffprobe",
+            TimeSourceKind::Export => "export",
+            TimeSourceKind::Filename => "filename",
+            TimeSourceKind::Directory => "directory",
+            TimeSourceKind::Mtime => "mtime",
+        }
+    }
+
+    /// Parse a configuration name (case-insensitive)
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim().to_lowercase();
+        Self::all().iter().copied().find(|kind| kind.name() == name)
+    }
+
+    /// All time sources in the default order
+    pub fn all() -> &'static [TimeSourceKind] {
+        &[
+            TimeSourceKind::Exif,
+            TimeSourceKind::Xmp,
+            TimeSourceKind::Container,
+            TimeSourceKind::Ffprobe,
+            TimeSourceKind::Export,
+            TimeSourceKind::Filename,
+            TimeSourceKind::Directory,
+            TimeSourceKind::Mtime,
+        ]
+    }
+}
+
+/// Format a time source chain as a comma-separated list
+pub fn format_time_sources(sources: &[TimeSourceKind]) -> String {
+    sources
+        .iter()
+        .map(|kind| kind.name())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Parse a comma-separated time source chain, returning the unknown name on error
+pub fn parse_time_sources(s: &str) -> Result<Vec<TimeSourceKind>, String> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| TimeSourceKind::from_name(name).ok_or_else(|| name.to_string()))
+        .collect()
+}
+
+/// Per file type overrides of the time source chain
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeSourcesByType {
+    /// Chain for photo files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub photos: Option<Vec<TimeSourceKind>>,
+
+    /// Chain for video files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub videos: Option<Vec<TimeSourceKind>>,
+
+    /// Chain for RAW files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw: Option<Vec<TimeSourceKind>>,
+}
+
+impl TimeSourcesByType {
+    /// Get the override for a file type, if any
+    pub fn get(&self, file_type: FileType) -> Option<&[TimeSourceKind]> {
+        match file_type {
+            FileType::Photos => self.photos.as_deref(),
+            FileType::Videos => self.videos.as_deref(),
+            FileType::Raw => self.raw.as_deref(),
+        }
+    }
+}
+
+/// Time source chain for files under one input directory
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DirTimeSources {
+    /// Directory the chain applies to, including subdirectories
+    pub path: PathBuf,
+
+    /// Time sources to try, in order
+    pub time_sources: Vec<TimeSourceKind>,
+}
+
+fn default_time_sources() -> Vec<TimeSourceKind> {
+    TimeSourceKind::all().to_vec()
+}
+
+fn default_conflict_threshold_hours() -> u64 {
+    24
+}
+
+fn default_near_duplicate_threshold() -> u32 {
+    6
+}
+
+/// Which timestamp wins when time sources disagree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictResolution {
+    /// First source in the chain that produced a timestamp
+    #[default]
+    First,
+    /// Timestamp most other sources agree with
+    Majority,
+    /// Timestamp of `preferred_source`, if it produced one
+    Prefer,
+}
+
+/// When a custom filename pattern is tried, relative to the built-in patterns
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PatternPrecedence {
+    /// Before all built-in patterns
+    #[default]
+    First,
+    /// Only when no built-in pattern matches
+    Last,
+}
+
+/// User-defined filename timestamp pattern
+///
+/// Exactly one of `format` or `regex` must be set. `format` is a chrono
+/// format string (e.g. `SCAN_%Y%m%d_%H%M%S`) matched anywhere in the name;
+/// `regex` uses the named groups `year`, `month`, `day`, `hour`, `minute`,
+/// `second`, `millis`, or `epoch` (a Unix timestamp in seconds or milliseconds).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilenamePattern {
+    /// Name shown in logs
+    pub name: String,
+
+    /// chrono format string
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+
+    /// Regular expression with named capture groups
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub regex: Option<String>,
+
+    /// Whether to try this pattern before or after the built-ins
+    #[serde(default)]
+    pub precedence: PatternPrecedence,
+
+    /// `regex`, compiled on first use
+    #[serde(skip)]
+    compiled: OnceLock<Option<Regex>>,
+}
+
+impl FilenamePattern {
+    /// Create a pattern from a chrono format string
+    pub fn with_format(name: &str, format: &str) -> Self {
+        Self::new(name, Some(format), None)
+    }
+
+    /// Create a pattern from a regular expression with named groups
+    pub fn with_regex(name: &str, regex: &str) -> Self {
+        Self::new(name, None, Some(regex))
+    }
+
+    fn new(name: &str, format: Option<&str>, regex: Option<&str>) -> Self {
+        Self {
+            name: name.to_string(),
+            format: format.map(String::from),
+            regex: regex.map(String::from),
+            precedence: PatternPrecedence::default(),
+            compiled: OnceLock::new(),
+        }
+    }
+
+    /// Get the compiled regular expression, if `regex` is set and valid
+    pub fn compiled_regex(&self) -> Option<&Regex> {
+        self.compiled
+            .get_or_init(|| self.regex.as_deref().and_then(|r| Regex::new(r).ok()))
+            .as_ref()
+    }
+
+    /// Check that the pattern is usable, returning the reason if not
+    pub fn validate(&self) -> Result<(), String> {
+        match (&self.format, &self.regex) {
+            (Some(format), None) => {
+                if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+                    return Err(format!("invalid format '{format}'"));
+                }
+                Ok(())
+            }
+            (None, Some(regex)) => {
+                let regex = Regex::new(regex).map_err(|e| e.to_string())?;
+                let groups: Vec<_> = regex.capture_names().flatten().collect();
+                let has_date = ["year", "month", "day"]
+                    .iter()
+                    .all(|group| groups.contains(group));
+                if !has_date && !groups.contains(&"epoch") {
+                    return Err(
+                        "regex needs the named groups year, month and day, or epoch".to_string()
+                    );
+                }
+                Ok(())
+            }
+            _ => Err("set exactly one of format or regex".to_string()),
+        }
+    }
+}
+
 /// File operation mode
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default, clap::ValueEnum)]
 #[serde(rename_all = "lowercase")]
@@ -245,6 +497,203 @@ impl EnumOption for FileOperation {
     }
 }
 
+/// Timezone override for a date range, e.g. a trip abroad
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimezoneWindow {
+    /// First day of the range (inclusive)
+    #[serde(deserialize_with = "deserialize_date")]
+    pub from: NaiveDate,
+
+    /// Last day of the range (inclusive)
+    #[serde(deserialize_with = "deserialize_date")]
+    pub to: NaiveDate,
+
+    /// IANA timezone in effect during the range
+    pub tz: Tz,
+}
+
+/// Bounds outside of which extracted timestamps are rejected
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlausibilityWindow {
+    /// Earliest accepted year
+    pub min_year: i32,
+
+    /// How far past the current time a timestamp may lie, in hours
+    pub future_tolerance_hours: i64,
+
+    /// Dates whose midnight is never accepted, e.g. the reset time of cameras
+    /// with a dead clock battery; other times on these days are kept
+    #[serde(deserialize_with = "deserialize_dates")]
+    pub sentinel_dates: Vec<NaiveDate>,
+}
+
+impl Default for PlausibilityWindow {
+    fn default() -> Self {
+        Self {
+            min_year: 1990,
+            future_tolerance_hours: 24,
+            sentinel_dates: [(1970, 1, 1), (1980, 1, 1), (2000, 1, 1)]
+                .into_iter()
+                .filter_map(|(y, m, d)| NaiveDate::from_ymd_opt(y, m, d))
+                .collect(),
+        }
+    }
+}
+
+impl PlausibilityWindow {
+    /// Check whether a timestamp lies inside the window, given the current local time
+    pub fn accepts(&self, timestamp: NaiveDateTime, now: NaiveDateTime) -> bool {
+        timestamp.year() >= self.min_year
+            && timestamp <= now + chrono::Duration::hours(self.future_tolerance_hours)
+            && !self.is_sentinel(timestamp)
+    }
+
+    /// Whether a timestamp is midnight on a sentinel date, ignoring sub-seconds
+    fn is_sentinel(&self, timestamp: NaiveDateTime) -> bool {
+        timestamp.num_seconds_from_midnight() == 0
+            && self.sentinel_dates.contains(&timestamp.date())
+    }
+}
+
+/// Clock correction for one camera body, e.g. a clock that ran fast
+///
+/// Every set key must match the EXIF tags of the photo (case-insensitive).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CameraClockOffset {
+    /// EXIF `Make`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub make: Option<String>,
+
+    /// EXIF `Model`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+
+    /// EXIF `BodySerialNumber`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub serial: Option<String>,
+
+    /// First day the correction applies (inclusive, camera time)
+    #[serde(
+        default,
+        deserialize_with = "deserialize_optional_date",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub from: Option<NaiveDate>,
+
+    /// Last day the correction applies (inclusive, camera time)
+    #[serde(
+        default,
+        deserialize_with = "deserialize_optional_date",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub to: Option<NaiveDate>,
+
+    /// Amount added to the camera time, written as "[+-]HH:MM[:SS]"
+    #[serde(with = "signed_hms")]
+    pub correction: Duration,
+}
+
+impl CameraClockOffset {
+    /// Check whether the entry applies to a camera at the given camera time
+    pub fn matches(&self, camera: &CameraInfo, timestamp: NaiveDateTime) -> bool {
+        let key_matches = |key: &Option<String>, value: &Option<String>| match key {
+            Some(key) => value
+                .as_deref()
+                .is_some_and(|value| value.trim().eq_ignore_ascii_case(key.trim())),
+            None => true,
+        };
+        let date = timestamp.date();
+
+        (self.make.is_some() || self.model.is_some() || self.serial.is_some())
+            && key_matches(&self.make, &camera.make)
+            && key_matches(&self.model, &camera.model)
+            && key_matches(&self.serial, &camera.serial)
+            && self.from.is_none_or(|from| date >= from)
+            && self.to.is_none_or(|to| date <= to)
+    }
+}
+
+/// (De)serialize a [`Duration`] as a signed "HH:MM[:SS]" string
+mod signed_hms {
+    use chrono::Duration;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        let sign = if *duration < Duration::zero() {
+            '-'
+        } else {
+            '+'
+        };
+        let seconds = duration.num_seconds().abs();
+        serializer.serialize_str(&format!(
+            "{sign}{:02}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        ))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        parse(&text).ok_or_else(|| {
+            serde::de::Error::custom(format!(
+                "invalid correction '{text}', expected [+-]HH:MM[:SS]"
+            ))
+        })
+    }
+
+    pub fn parse(s: &str) -> Option<Duration> {
+        let s = s.trim();
+        let (sign, rest) = match s.strip_prefix('-') {
+            Some(rest) => (-1, rest),
+            None => (1, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let mut parts = rest.split(':').map(|part| part.parse::<i64>().ok());
+        let hours = parts.next()??;
+        let minutes = parts.next()??;
+        let seconds = parts.next().unwrap_or(Some(0))?;
+        if parts.next().is_some() || minutes > 59 || seconds > 59 {
+            return None;
+        }
+        Some(Duration::seconds(
+            sign * (hours * 3600 + minutes * 60 + seconds),
+        ))
+    }
+}
+
+/// Deserialize an optional date, see [`deserialize_date`]
+fn deserialize_optional_date<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<NaiveDate>, D::Error> {
+    deserialize_date(deserializer).map(Some)
+}
+
+/// Deserialize a date written either as a TOML date or a "YYYY-MM-DD" string
+fn deserialize_date<'de, D: Deserializer<'de>>(deserializer: D) -> Result<NaiveDate, D::Error> {
+    date_from_toml(toml::Value::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+}
+
+/// Deserialize a list of dates, see [`deserialize_date`]
+fn deserialize_dates<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<NaiveDate>, D::Error> {
+    Vec::<toml::Value>::deserialize(deserializer)?
+        .into_iter()
+        .map(date_from_toml)
+        .collect::<Result<_, _>>()
+        .map_err(serde::de::Error::custom)
+}
+
+fn date_from_toml(value: toml::Value) -> Result<NaiveDate, String> {
+    let text = match value {
+        toml::Value::Datetime(datetime) => datetime.to_string(),
+        toml::Value::String(text) => text,
+        other => return Err(format!("expected a date, found {}", other.type_str())),
+    };
+    NaiveDate::parse_from_str(&text, "%Y-%m-%d").map_err(|e| e.to_string())
+}
+
 /// Configuration for the gallery sorter
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -272,12 +721,24 @@ pub struct Config {
     #[serde(default)]
     pub classify_by_type: bool,
 
+    /// Classify by source app (adds WeChat/Telegram/... subdirectory for recognized filenames)
+    #[serde(default)]
+    pub classify_by_app: bool,
+
     /// File operation mode
     pub operation: FileOperation,
 
     /// Enable file deduplication
     pub deduplicate: bool,
 
+    /// Skip images that look like a larger image (re-saved or resized copies)
+    #[serde(default)]
+    pub near_duplicates: bool,
+
+    /// Perceptual hashes differing in at most this many of 64 bits are near duplicates
+    #[serde(default = "default_near_duplicate_threshold")]
+    pub near_duplicate_threshold: u32,
+
     /// State file path for incremental processing
     pub state_file: Option<PathBuf>,
 
@@ -287,6 +748,66 @@ pub struct Config {
     /// Large file threshold in bytes (files larger than this use sampled hashing)
     pub large_file_threshold: u64,
 
+    /// IANA timezone for converting UTC timestamps (None = system timezone)
+    #[serde(default)]
+    pub timezone: Option<Tz>,
+
+    /// Date-range timezone overrides for UTC timestamps, checked in order
+    #[serde(default)]
+    pub timezone_schedule: Vec<TimezoneWindow>,
+
+    /// Time sources to try, in order
+    #[serde(default = "default_time_sources")]
+    pub time_sources: Vec<TimeSourceKind>,
+
+    /// File system time used by the `mtime` time source
+    #[serde(default)]
+    pub file_time: FileTimeKind,
+
+    /// Per file type overrides of `time_sources`
+    #[serde(default)]
+    pub time_sources_by_type: TimeSourcesByType,
+
+    /// Per input directory overrides of `time_sources` (take precedence over file type)
+    #[serde(default)]
+    pub time_sources_by_dir: Vec<DirTimeSources>,
+
+    /// User-defined filename timestamp patterns
+    #[serde(default)]
+    pub filename_patterns: Vec<FilenamePattern>,
+
+    /// Timestamps outside this window fall through to the next time source
+    #[serde(default)]
+    pub plausibility: PlausibilityWindow,
+
+    /// Run every time source for each file and write a conflict report
+    #[serde(default)]
+    pub audit: bool,
+
+    /// Audit report path (None = default in output directory)
+    #[serde(default)]
+    pub audit_report: Option<PathBuf>,
+
+    /// Sources disagreeing by more than this many hours are a conflict
+    #[serde(default = "default_conflict_threshold_hours")]
+    pub conflict_threshold_hours: u64,
+
+    /// Which timestamp to use when sources conflict
+    #[serde(default)]
+    pub conflict_resolution: ConflictResolution,
+
+    /// Source used by the `prefer` conflict resolution
+    #[serde(default)]
+    pub preferred_source: Option<TimeSourceKind>,
+
+    /// Per camera clock corrections, the first matching entry wins
+    #[serde(default)]
+    pub camera_clock_offsets: Vec<CameraClockOffset>,
+
+    /// Interpolate times of undated files from camera sequence numbers
+    #[serde(default)]
+    pub interpolate_sequences: bool,
+
     /// Dry run mode - don't actually move/copy files
     pub dry_run: bool,
 
@@ -313,11 +834,29 @@ impl Default for Config {
             classification: ClassificationRule::default(),
             month_format: MonthFormat::default(),
             classify_by_type: false,
+            classify_by_app: false,
             operation: FileOperation::default(),
             deduplicate: true,
+            near_duplicates: false,
+            near_duplicate_threshold: default_near_duplicate_threshold(),
             state_file: None,
             threads: 0,                              // Auto-detect
             large_file_threshold: 100 * 1024 * 1024, // 100MB
+            timezone: None,
+            timezone_schedule: vec![],
+            time_sources: default_time_sources(),
+            file_time: FileTimeKind::default(),
+            time_sources_by_type: TimeSourcesByType::default(),
+            time_sources_by_dir: vec![],
+            filename_patterns: vec![],
+            plausibility: PlausibilityWindow::default(),
+            audit: false,
+            audit_report: None,
+            conflict_threshold_hours: default_conflict_threshold_hours(),
+            conflict_resolution: ConflictResolution::default(),
+            preferred_source: None,
+            camera_clock_offsets: vec![],
+            interpolate_sequences: false,
             dry_run: false,
             verbose: false,
             image_extensions: vec![
@@ -342,6 +881,8 @@ impl Default for Config {
                 "flv".into(),
                 "m4v".into(),
                 "3gp".into(),
+                "mts".into(),
+                "m2ts".into(),
             ],
             raw_extensions: vec![
                 "raw".into(),
@@ -417,6 +958,46 @@ impl Config {
         }
     }
 
+    /// Get the time source chain for a file
+    ///
+    /// The most specific entry of `time_sources_by_dir` wins, then the
+    /// file type override, then the global `time_sources`.
+    pub fn time_sources_for(&self, path: &Path) -> &[TimeSourceKind] {
+        if let Some(entry) = self
+            .time_sources_by_dir
+            .iter()
+            .filter(|entry| path.starts_with(&entry.path))
+            .max_by_key(|entry| entry.path.components().count())
+        {
+            return &entry.time_sources;
+        }
+
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+        self.get_file_type(ext)
+            .and_then(|file_type| self.time_sources_by_type.get(file_type))
+            .unwrap_or(&self.time_sources)
+    }
+
+    /// Get the clock correction for a camera at the given camera time
+    pub fn camera_clock_correction(
+        &self,
+        camera: &CameraInfo,
+        timestamp: NaiveDateTime,
+    ) -> Option<Duration> {
+        self.camera_clock_offsets
+            .iter()
+            .find(|entry| entry.matches(camera, timestamp))
+            .map(|entry| entry.correction)
+    }
+
+    /// Get the timezone rules for UTC timestamps without a capture location
+    pub fn zone_rules(&self) -> ZoneRules<'_> {
+        ZoneRules {
+            home: self.timezone,
+            schedule: &self.timezone_schedule,
+        }
+    }
+
     /// Get state file path, using default if not specified
     pub fn get_state_file(&self) -> PathBuf {
         self.state_file
@@ -424,6 +1005,25 @@ impl Config {
             .unwrap_or_else(|| self.output_dir.join(".gallery_sorter_state.json"))
     }
 
+    /// Get metadata cache path, next to the state file
+    pub fn get_metadata_cache_file(&self) -> PathBuf {
+        self.get_state_file()
+            .with_file_name(".gallery_sorter_metadata_cache.json")
+    }
+
+    /// Get content hash cache path, next to the state file
+    pub fn get_hash_cache_file(&self) -> PathBuf {
+        self.get_state_file()
+            .with_file_name(".gallery_sorter_hash_cache.json")
+    }
+
+    /// Get audit report path, using default if not specified
+    pub fn get_audit_report_file(&self) -> PathBuf {
+        self.audit_report
+            .clone()
+            .unwrap_or_else(|| self.output_dir.join(".gallery_sorter_time_audit.json"))
+    }
+
     /// Load configuration from a TOML file
     pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
         let path = path.as_ref();
@@ -511,12 +1111,24 @@ month_format = "nested"
 # - true: 2024/01/Photos/photo.jpg, 2024/01/Photos/Raw/photo.arw
 classify_by_type = false
 
+# Classify by source app, for filenames of WhatsApp, WeChat, Telegram, Signal,
+# Pixel and Samsung (adds a subdirectory after any file type folder)
+# - true: 2023/05/WeChat/mmexport1683000000000.jpg
+classify_by_app = false
+
 # File operation: "copy", "move", "symlink", or "hardlink"
 operation = "copy"
 
 # Enable file deduplication (skip duplicate files)
 deduplicate = true
 
+# Skip JPEG/PNG/WebP images that look like a larger image, such as copies
+# re-saved by a messenger or resized for sharing (the largest image is kept)
+near_duplicates = false
+
+# Perceptual hashes differing in at most this many of 64 bits are near duplicates
+near_duplicate_threshold = 6
+
 # Number of threads for parallel processing (0 = auto-detect)
 threads = 0
 
@@ -524,6 +1136,39 @@ threads = 0
 # Default: 100MB = 104857600 bytes
 large_file_threshold = 104857600
 
+# IANA timezone used to convert UTC timestamps (video metadata, file times)
+# when the capture location is unknown. DST is applied per timestamp.
+# Leave commented to use the system timezone.
+# timezone = "Europe/Berlin"
+
+# Time sources to try, in order: "exif", "xmp" (DSC_0001.ARW.xmp or
+# DSC_0001.xmp sidecar), "container", "ffprobe", "export" (Google Takeout
+# IMG_1234.jpg.json), "filename", "directory" (dated folder names such as
+# "2009-07 Lake Trip"), "mtime"
+# Sources that don't apply to a file (e.g. exif for videos) are skipped.
+time_sources = ["exif", "xmp", "container", "ffprobe", "export", "filename", "directory", "mtime"]
+
+# File system time used by the "mtime" source: "mtime", "btime" (birth time,
+# survives edits; falls back to mtime where the file system lacks it) or "oldest"
+file_time = "mtime"
+
+# Audit mode: run every time source for each file and write a report of all
+# candidate times, flagging files whose sources disagree by more than
+# conflict_threshold_hours (file system time is listed but never a conflict)
+audit = false
+# audit_report = "D:/Sorted/time_audit.json"
+conflict_threshold_hours = 24
+
+# Timestamp used when sources conflict: "first", "majority", or "prefer"
+# ("prefer" uses preferred_source when it produced a timestamp)
+conflict_resolution = "first"
+# preferred_source = "filename"
+
+# Give files with only a file system time (e.g. DSC_0043.JPG without EXIF) a
+# time interpolated between the nearest dated files of the same folder and
+# camera sequence, such as DSC_0042.JPG and DSC_0045.JPG
+interpolate_sequences = false
+
 # Dry run mode - show what would be done without actually doing it
 dry_run = false
 
@@ -533,8 +1178,53 @@ verbose = false
 # Supported file extensions (customize as needed)
 # EXIF extraction supported: jpg, jpeg, png, webp, heic, heif, avif, tiff, tif
 image_extensions = ["jpg", "jpeg", "png", "gif", "bmp", "webp", "heic", "heif", "avif", "tiff", "tif"]
-video_extensions = ["mp4", "mov", "avi", "mkv", "wmv", "flv", "m4v", "3gp"]
+video_extensions = ["mp4", "mov", "avi", "mkv", "wmv", "flv", "m4v", "3gp", "mts", "m2ts"]
 raw_extensions = ["raw", "arw", "cr2", "cr3", "nef", "orf", "rw2", "dng", "raf", "srw", "pef"]
+
+# Timezone overrides for date ranges, e.g. trips abroad (dates are inclusive)
+# [[timezone_schedule]]
+# from = 2024-07-01
+# to = 2024-07-20
+# tz = "Asia/Tokyo"
+
+# Time source overrides per file type ("photos", "videos", "raw")
+# [time_sources_by_type]
+# photos = ["exif", "filename", "mtime"]
+
+# Time source overrides per input directory (most specific path wins)
+# [[time_sources_by_dir]]
+# path = "D:/Photos/Scans"
+# time_sources = ["filename", "exif", "mtime"]
+
+# Extra filename timestamp patterns, using either a chrono format or a regex
+# with named groups (year, month, day, hour, minute, second, millis, epoch).
+# precedence: "first" (before the built-in patterns, default) or "last"
+# [[filename_patterns]]
+# name = "scanner"
+# format = "SCAN_%Y%m%d_%H%M%S"
+#
+# [[filename_patterns]]
+# name = "dashcam"
+# regex = '^(?P<year>\d{4})_(?P<month>\d{2})(?P<day>\d{2})_(?P<hour>\d{2})(?P<minute>\d{2})(?P<second>\d{2})_\d+[FR]$'
+# precedence = "last"
+
+# Timestamps outside this window are rejected and the next time source is tried
+# Sentinel dates are only rejected at exactly midnight
+# [plausibility]
+# min_year = 1990
+# future_tolerance_hours = 24
+# sentinel_dates = ["1970-01-01", "1980-01-01", "2000-01-01"]
+
+# Clock corrections per camera body, matched on the EXIF make, model and
+# serial number, optionally limited to a date range (camera time).
+# correction is added to the camera time: a clock 1h 12m fast needs "-01:12"
+# [[camera_clock_offsets]]
+# make = "Canon"
+# model = "Canon EOS R6"
+# serial = "012345678901"
+# from = 2022-03-01
+# to = 2024-02-29
+# correction = "-01:12"
 "#
         .to_string()
     }
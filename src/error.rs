@@ -24,6 +24,9 @@ pub enum Error {
     #[error("Failed to extract video metadata from {path}: {message}")]
     VideoMetadata { path: PathBuf, message: String },
 
+    #[error("Failed to decode image {path}: {message}")]
+    ImageDecode { path: PathBuf, message: String },
+
     #[error("File hash computation failed for {path}: {message}")]
     HashComputation { path: PathBuf, message: String },
 
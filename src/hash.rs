@@ -3,22 +3,34 @@
 //! For regular files, computes the full file hash.
 //! For large files (videos), samples 1MB from head, middle, and tail
 //! to compute a fast approximate hash.
+//!
+//! Content hashes are 128-bit xxh3. A matching hash only nominates two files
+//! as duplicates: sampled hashes ignore most of the file, so
+//! [`files_identical`] confirms the match byte by byte before a file is
+//! dropped.
+//!
+//! Files are streamed through the hasher with a fixed buffer, so memory use
+//! does not depend on file size. The result equals the one-shot xxh3 hash of
+//! the same bytes.
 
 use crate::error::{Error, Result};
 use std::fs::File;
-use std::io::{Read, Seek, SeekFrom};
+use std::io::{self, Read, Seek, SeekFrom};
 use std::path::Path;
 use tracing::trace;
-use xxhash_rust::xxh3::xxh3_64;
+use xxhash_rust::xxh3::{Xxh3, xxh3_64};
 
 /// Size of each sample chunk for large files (1MB)
 const SAMPLE_SIZE: u64 = 1024 * 1024;
 
+/// Size of the read buffer (64KB)
+const BUFFER_SIZE: usize = 64 * 1024;
+
 /// Compute hash for a file
 ///
 /// For files smaller than the threshold, computes full file hash.
 /// For larger files, samples from head, middle, and tail.
-pub fn compute_file_hash(path: &Path, large_file_threshold: u64) -> Result<u64> {
+pub fn compute_file_hash(path: &Path, large_file_threshold: u64) -> Result<u128> {
     let metadata = std::fs::metadata(path)?;
     let file_size = metadata.len();
 
@@ -30,20 +42,43 @@ pub fn compute_file_hash(path: &Path, large_file_threshold: u64) -> Result<u64>
 }
 
 /// Compute full file hash using xxHash3
-fn compute_full_hash(path: &Path) -> Result<u64> {
+fn compute_full_hash(path: &Path) -> Result<u128> {
     let mut file = File::open(path)?;
-    let mut buffer = Vec::new();
-    file.read_to_end(&mut buffer)
-        .map_err(|e| Error::HashComputation {
-            path: path.to_path_buf(),
-            message: format!("Failed to read file: {}", e),
-        })?;
+    let mut hasher = Xxh3::new();
+    let mut buffer = vec![0u8; BUFFER_SIZE];
 
-    let hash = xxh3_64(&buffer);
+    loop {
+        let read = match file.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => {
+                return Err(Error::HashComputation {
+                    path: path.to_path_buf(),
+                    message: format!("Failed to read file: {}", e),
+                });
+            }
+        };
+        hasher.update(&buffer[..read]);
+    }
+
+    let hash = hasher.digest128();
     trace!(?path, hash, "Computed full file hash");
     Ok(hash)
 }
 
+/// Feed the next `len` bytes of `file` into `hasher`
+fn hash_exact(file: &mut File, len: u64, hasher: &mut Xxh3, buffer: &mut [u8]) -> io::Result<()> {
+    let mut remaining = len;
+    while remaining > 0 {
+        let chunk = remaining.min(buffer.len() as u64) as usize;
+        file.read_exact(&mut buffer[..chunk])?;
+        hasher.update(&buffer[..chunk]);
+        remaining -= chunk as u64;
+    }
+    Ok(())
+}
+
 /// Compute sampled hash for large files
 ///
 /// Takes 1MB samples from:
@@ -52,22 +87,22 @@ fn compute_full_hash(path: &Path) -> Result<u64> {
 /// - End of file
 ///
 /// Also incorporates file size into the hash for additional uniqueness
-fn compute_sampled_hash(path: &Path, file_size: u64) -> Result<u64> {
+fn compute_sampled_hash(path: &Path, file_size: u64) -> Result<u128> {
     let mut file = File::open(path)?;
-    let mut hasher_data = Vec::with_capacity((SAMPLE_SIZE * 3 + 8) as usize);
+    let mut hasher = Xxh3::new();
+    let mut buffer = vec![0u8; BUFFER_SIZE];
 
     // Include file size in hash
-    hasher_data.extend_from_slice(&file_size.to_le_bytes());
+    hasher.update(&file_size.to_le_bytes());
 
     // Read head sample
     let head_size = std::cmp::min(SAMPLE_SIZE, file_size);
-    let mut head_buffer = vec![0u8; head_size as usize];
-    file.read_exact(&mut head_buffer)
-        .map_err(|e| Error::HashComputation {
+    hash_exact(&mut file, head_size, &mut hasher, &mut buffer).map_err(|e| {
+        Error::HashComputation {
             path: path.to_path_buf(),
             message: format!("Failed to read file head: {}", e),
-        })?;
-    hasher_data.extend_from_slice(&head_buffer);
+        }
+    })?;
 
     // Read middle sample (if file is large enough)
     if file_size > SAMPLE_SIZE * 2 {
@@ -77,13 +112,12 @@ fn compute_sampled_hash(path: &Path, file_size: u64) -> Result<u64> {
                 path: path.to_path_buf(),
                 message: format!("Failed to seek to middle: {}", e),
             })?;
-        let mut middle_buffer = vec![0u8; SAMPLE_SIZE as usize];
-        file.read_exact(&mut middle_buffer)
-            .map_err(|e| Error::HashComputation {
+        hash_exact(&mut file, SAMPLE_SIZE, &mut hasher, &mut buffer).map_err(|e| {
+            Error::HashComputation {
                 path: path.to_path_buf(),
                 message: format!("Failed to read file middle: {}", e),
-            })?;
-        hasher_data.extend_from_slice(&middle_buffer);
+            }
+        })?;
     }
 
     // Read tail sample (if file is large enough)
@@ -94,24 +128,69 @@ fn compute_sampled_hash(path: &Path, file_size: u64) -> Result<u64> {
                 path: path.to_path_buf(),
                 message: format!("Failed to seek to tail: {}", e),
             })?;
-        let mut tail_buffer = vec![0u8; SAMPLE_SIZE as usize];
-        file.read_exact(&mut tail_buffer)
-            .map_err(|e| Error::HashComputation {
+        hash_exact(&mut file, SAMPLE_SIZE, &mut hasher, &mut buffer).map_err(|e| {
+            Error::HashComputation {
                 path: path.to_path_buf(),
                 message: format!("Failed to read file tail: {}", e),
-            })?;
-        hasher_data.extend_from_slice(&tail_buffer);
+            }
+        })?;
     }
 
-    let hash = xxh3_64(&hasher_data);
+    let hash = hasher.digest128();
     trace!(?path, file_size, hash, "Computed sampled file hash");
     Ok(hash)
 }
 
+/// Compare two files byte by byte
+///
+/// Reads both files in fixed-size chunks and stops at the first difference.
+pub fn files_identical(a: &Path, b: &Path) -> Result<bool> {
+    let (mut file_a, mut file_b) = (File::open(a)?, File::open(b)?);
+    if file_a.metadata()?.len() != file_b.metadata()?.len() {
+        return Ok(false);
+    }
+
+    let mut buffer_a = vec![0u8; BUFFER_SIZE];
+    let mut buffer_b = vec![0u8; BUFFER_SIZE];
+    let compare_error = |e: io::Error| Error::HashComputation {
+        path: a.to_path_buf(),
+        message: format!("Failed to compare with {}: {}", b.display(), e),
+    };
+    loop {
+        let read_a = fill_buffer(&mut file_a, &mut buffer_a).map_err(compare_error)?;
+        let read_b = fill_buffer(&mut file_b, &mut buffer_b).map_err(compare_error)?;
+        if buffer_a[..read_a] != buffer_b[..read_b] {
+            trace!(?a, ?b, "Files differ");
+            return Ok(false);
+        }
+        if read_a < BUFFER_SIZE {
+            return Ok(true);
+        }
+    }
+}
+
+/// Read until `buffer` is full or the file ends, returning the bytes read
+fn fill_buffer(file: &mut File, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match file.read(&mut buffer[filled..]) {
+            Ok(0) => break,
+            Ok(read) => filled += read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
 /// Compute a quick hash based on file metadata only
 /// Used for incremental processing to detect file changes
 pub fn compute_metadata_hash(path: &Path) -> Result<u64> {
-    let metadata = std::fs::metadata(path)?;
+    Ok(hash_metadata(&std::fs::metadata(path)?))
+}
+
+/// Hash the size and modification time of already read file metadata
+pub fn hash_metadata(metadata: &std::fs::Metadata) -> u64 {
     let mut data = Vec::new();
 
     // Include file size
@@ -125,7 +204,7 @@ pub fn compute_metadata_hash(path: &Path) -> Result<u64> {
         data.extend_from_slice(&duration.subsec_nanos().to_le_bytes());
     }
 
-    Ok(xxh3_64(&data))
+    xxh3_64(&data)
 }
 
 #[cfg(test)]
@@ -133,6 +212,7 @@ mod tests {
     use super::*;
     use std::io::Write;
     use tempfile::NamedTempFile;
+    use xxhash_rust::xxh3::xxh3_128;
 
     #[test]
     fn test_compute_full_hash() {
@@ -167,4 +247,53 @@ mod tests {
 
         assert_ne!(hash1, hash2);
     }
+
+    #[test]
+    fn test_streaming_matches_one_shot() {
+        let data: Vec<u8> = (0..SAMPLE_SIZE * 3 + 12345)
+            .map(|i| (i % 251) as u8)
+            .collect();
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(&data).unwrap();
+        file.flush().unwrap();
+
+        assert_eq!(compute_full_hash(file.path()).unwrap(), xxh3_128(&data));
+
+        // The sampled hash covers size, head, middle and tail
+        let size = data.len() as u64;
+        let middle = ((size - SAMPLE_SIZE) / 2) as usize;
+        let sample = SAMPLE_SIZE as usize;
+        let mut sampled = size.to_le_bytes().to_vec();
+        sampled.extend_from_slice(&data[..sample]);
+        sampled.extend_from_slice(&data[middle..middle + sample]);
+        sampled.extend_from_slice(&data[data.len() - sample..]);
+        assert_eq!(
+            compute_sampled_hash(file.path(), size).unwrap(),
+            xxh3_128(&sampled)
+        );
+    }
+
+    #[test]
+    fn test_files_identical() {
+        let data: Vec<u8> = (0..BUFFER_SIZE * 2 + 7).map(|i| (i % 251) as u8).collect();
+        let mut changed = data.clone();
+        *changed.last_mut().unwrap() ^= 1;
+
+        let write = |bytes: &[u8]| {
+            let mut file = NamedTempFile::new().unwrap();
+            file.write_all(bytes).unwrap();
+            file.flush().unwrap();
+            file
+        };
+        let (a, b, c, d) = (
+            write(&data),
+            write(&data),
+            write(&changed),
+            write(&data[1..]),
+        );
+
+        assert!(files_identical(a.path(), b.path()).unwrap());
+        assert!(!files_identical(a.path(), c.path()).unwrap());
+        assert!(!files_identical(a.path(), d.path()).unwrap());
+    }
 }
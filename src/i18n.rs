@@ -49,6 +49,16 @@ mod tests {
         assert!(locale == "en" || locale == "zh-CN");
     }
 
+    #[test]
+    fn test_time_sources_error_lists_all_sources() {
+        for locale in ["en", "zh-CN"] {
+            let message = rust_i18n::t!("time_sources_invalid_error", locale = locale);
+            for kind in crate::config::TimeSourceKind::all() {
+                assert!(message.contains(kind.name()), "{locale}: {}", kind.name());
+            }
+        }
+    }
+
     #[test]
     fn test_init_locale() {
         let locale = init_locale();
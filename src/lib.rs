@@ -3,9 +3,10 @@
 //! This library provides functionality for organizing photos and videos
 //! based on their creation time with support for:
 //! - EXIF metadata extraction for images
-//! - FFprobe-based metadata extraction for videos
+//! - Native MP4/MOV, AVI, Matroska and AVCHD metadata extraction, with FFprobe as fallback for videos
 //! - Filename timestamp parsing
 //! - xxHash-based file deduplication
+//! - Perceptual near-duplicate detection for images
 //! - Parallel processing with Rayon
 //! - Incremental processing
 //! - Interactive CLI mode with progress display
@@ -13,12 +14,14 @@
 // Initialize i18n with locale files
 rust_i18n::i18n!("locales", fallback = "en");
 
+pub mod cache;
 pub mod cli;
 pub mod config;
 pub mod error;
 pub mod hash;
 pub mod i18n;
 pub mod os;
+pub mod perceptual;
 pub mod process;
 pub mod state;
 pub mod time;
@@ -7,6 +7,7 @@
 use anyhow::Result;
 use chrono::Local;
 use clap::Parser;
+use gallery_sorter::config::ConflictResolution;
 use gallery_sorter::{Cli, Config, Processor, TuiApp, init_locale, should_run_interactive};
 use std::path::{Path, PathBuf};
 use tracing::{Level, error, info};
@@ -218,6 +219,7 @@ fn run_cli_mode() -> Result<()> {
     }
 
     let dry_run = config.dry_run;
+    let audit_report = config.audit.then(|| config.get_audit_report_file());
 
     // Log to file location
     info!(log_file = %log_path.display(), "Log file location");
@@ -249,18 +251,49 @@ fn run_cli_mode() -> Result<()> {
             let skipped = stats.skipped.load(std::sync::atomic::Ordering::Relaxed);
             let duplicates = stats.duplicates.load(std::sync::atomic::Ordering::Relaxed);
             let failed_count = stats.failed.load(std::sync::atomic::Ordering::Relaxed);
+            let clock_corrected = stats
+                .clock_corrected
+                .load(std::sync::atomic::Ordering::Relaxed);
+            let interpolated = stats
+                .interpolated
+                .load(std::sync::atomic::Ordering::Relaxed);
+            let near_duplicates = stats
+                .near_duplicates
+                .load(std::sync::atomic::Ordering::Relaxed);
 
             // Print stats with colors
             print_blank();
             print_stat(&stat_processed, &processed.to_string(), CliTheme::SUCCESS);
             print_stat(&stat_skipped, &skipped.to_string(), CliTheme::WARNING);
             print_stat(&stat_duplicates, &duplicates.to_string(), CliTheme::ACCENT);
+            if near_duplicates > 0 {
+                print_stat(
+                    &t!("stat_near_duplicates"),
+                    &near_duplicates.to_string(),
+                    CliTheme::ACCENT,
+                );
+            }
             print_stat(&stat_failed, &failed_count.to_string(), CliTheme::ERROR);
+            if clock_corrected > 0 {
+                print_stat(
+                    &t!("stat_clock_corrected"),
+                    &clock_corrected.to_string(),
+                    CliTheme::ACCENT,
+                );
+            }
+            if interpolated > 0 {
+                print_stat(
+                    &t!("stat_interpolated"),
+                    &interpolated.to_string(),
+                    CliTheme::ACCENT,
+                );
+            }
             print_blank();
 
             // Store translations for results
             let already_processed = t!("already_processed");
             let duplicate_of = t!("duplicate_of");
+            let near_duplicate_of = t!("near_duplicate_of");
             let unknown_error = t!("unknown_error");
 
             // Print detailed results if verbose
@@ -305,6 +338,19 @@ fn run_cli_mode() -> Result<()> {
                                 &format!("{}: {}", duplicate_of, dest),
                             );
                         }
+                        gallery_sorter::process::ProcessingStatus::NearDuplicate => {
+                            let dest = result
+                                .destination
+                                .as_ref()
+                                .map(|p| p.display().to_string())
+                                .unwrap_or_default();
+                            print_result(
+                                "≈",
+                                CliTheme::ACCENT,
+                                &result.source.display().to_string(),
+                                &format!("{}: {}", near_duplicate_of, dest),
+                            );
+                        }
                         gallery_sorter::process::ProcessingStatus::Failed => {
                             let error_msg = result.error.as_deref().unwrap_or(&unknown_error);
                             print_result(
@@ -356,6 +402,20 @@ fn run_cli_mode() -> Result<()> {
                 }
             }
 
+            if let (Some(report), Some(conflicts)) = (&audit_report, processor.time_conflicts()) {
+                print_separator();
+                print_key_value(&t!("cli_audit_report"), &report.display().to_string(), None);
+                print_key_value(
+                    &t!("cli_audit_conflicts"),
+                    &conflicts.to_string(),
+                    Some(if conflicts > 0 {
+                        CliTheme::WARNING
+                    } else {
+                        CliTheme::SUCCESS
+                    }),
+                );
+            }
+
             if dry_run {
                 print_separator();
                 print_warning(&t!("cli_dry_run_notice"));
@@ -551,5 +611,41 @@ fn validate_config(config: &Config) -> Result<()> {
         }
     }
 
+    for window in &config.timezone_schedule {
+        if window.from > window.to {
+            anyhow::bail!(
+                "{} {} > {} ({})",
+                t!("cli_timezone_window_invalid"),
+                window.from,
+                window.to,
+                window.tz
+            );
+        }
+    }
+
+    if config.conflict_resolution == ConflictResolution::Prefer && config.preferred_source.is_none()
+    {
+        anyhow::bail!("{}", t!("cli_preferred_source_missing"));
+    }
+
+    for entry in &config.camera_clock_offsets {
+        let no_camera = entry.make.is_none() && entry.model.is_none() && entry.serial.is_none();
+        let bad_range = matches!((entry.from, entry.to), (Some(from), Some(to)) if from > to);
+        if no_camera || bad_range {
+            anyhow::bail!("{} {:?}", t!("cli_camera_clock_offset_invalid"), entry);
+        }
+    }
+
+    for pattern in &config.filename_patterns {
+        if let Err(reason) = pattern.validate() {
+            anyhow::bail!(
+                "{} '{}': {}",
+                t!("cli_filename_pattern_invalid"),
+                pattern.name,
+                reason
+            );
+        }
+    }
+
     Ok(())
 }
@@ -41,3 +41,17 @@ pub fn needs_elevation_for_symlink() -> bool {
 pub fn needs_elevation_for_symlink() -> bool {
     false
 }
+
+/// Get the inode number of a file, identifying it across renames.
+#[cfg(unix)]
+pub fn file_id(metadata: &std::fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.ino()
+}
+
+/// Get the inode number of a file, identifying it across renames.
+/// Windows has no stable equivalent, so all files share the id 0.
+#[cfg(windows)]
+pub fn file_id(_metadata: &std::fs::Metadata) -> u64 {
+    0
+}
@@ -0,0 +1,194 @@
+//! Perceptual hashing for near-duplicate images
+//!
+//! A photo re-saved by a messenger at lower quality, or resized for sharing,
+//! has none of its bytes in common with the original, so content hashes
+//! can't match it. The difference hash (dHash) of its pixels survives that:
+//! the image is shrunk to 9x8 grayscale and each of the 64 bits records
+//! whether a pixel is brighter than its right neighbour. Images whose hashes
+//! differ in at most `near_duplicate_threshold` bits are near duplicates.
+//!
+//! JPEG, PNG and WebP files are decoded; other formats are left to exact
+//! deduplication.
+
+use crate::error::{Error, Result};
+use image::{DynamicImage, ImageDecoder, ImageReader};
+use std::path::Path;
+use tracing::trace;
+
+/// Extensions of the image formats that are decoded
+const DECODED_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "webp"];
+
+/// Width of the shrunk image, one more than the bits per row
+const HASH_WIDTH: u32 = 9;
+
+/// Height of the shrunk image, one row per 8 bits
+const HASH_HEIGHT: u32 = 8;
+
+/// Perceptual hash and resolution of a decoded image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageFingerprint {
+    /// Difference hash of the pixels
+    pub hash: u64,
+    /// Width after applying the EXIF orientation
+    pub width: u32,
+    /// Height after applying the EXIF orientation
+    pub height: u32,
+}
+
+impl ImageFingerprint {
+    /// Number of pixels
+    pub fn resolution(&self) -> u64 {
+        self.width as u64 * self.height as u64
+    }
+
+    /// Number of differing hash bits
+    pub fn distance(&self, other: &Self) -> u32 {
+        (self.hash ^ other.hash).count_ones()
+    }
+}
+
+/// Whether a file is in a format that is decoded for perceptual hashing
+pub fn is_decodable(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|ext| {
+            DECODED_EXTENSIONS
+                .iter()
+                .any(|decoded| decoded.eq_ignore_ascii_case(ext))
+        })
+}
+
+/// Decode an image and compute its fingerprint
+pub fn compute_fingerprint(path: &Path) -> Result<ImageFingerprint> {
+    let decode_error = |e: image::ImageError| Error::ImageDecode {
+        path: path.to_path_buf(),
+        message: e.to_string(),
+    };
+
+    let mut decoder = ImageReader::open(path)?
+        .with_guessed_format()?
+        .into_decoder()
+        .map_err(decode_error)?;
+    // Messengers usually rotate the pixels and drop the EXIF orientation
+    let orientation = decoder.orientation().map_err(decode_error)?;
+    let mut image = DynamicImage::from_decoder(decoder).map_err(decode_error)?;
+    image.apply_orientation(orientation);
+
+    let fingerprint = ImageFingerprint {
+        hash: difference_hash(&image),
+        width: image.width(),
+        height: image.height(),
+    };
+    trace!(?path, hash = %format!("{:016x}", fingerprint.hash), "Computed perceptual hash");
+    Ok(fingerprint)
+}
+
+/// Difference hash: one bit per horizontally adjacent pixel pair of the shrunk image
+fn difference_hash(image: &DynamicImage) -> u64 {
+    let small = image.thumbnail_exact(HASH_WIDTH, HASH_HEIGHT).into_luma8();
+    let mut hash = 0u64;
+    for y in 0..HASH_HEIGHT {
+        for x in 0..HASH_WIDTH - 1 {
+            let brighter = small.get_pixel(x, y)[0] > small.get_pixel(x + 1, y)[0];
+            hash = (hash << 1) | brighter as u64;
+        }
+    }
+    hash
+}
+
+/// Match each image to a kept image it nearly duplicates
+///
+/// `images` is sorted best first. An image within `threshold` bits of an
+/// image kept before it joins that image's cluster, otherwise it is kept
+/// itself, so clusters never chain through intermediate images. Returns
+/// `(near duplicate, kept image)` index pairs.
+pub fn find_near_duplicates(images: &[ImageFingerprint], threshold: u32) -> Vec<(usize, usize)> {
+    let mut kept: Vec<usize> = Vec::new();
+    let mut near_duplicates = Vec::new();
+
+    for (i, image) in images.iter().enumerate() {
+        match kept
+            .iter()
+            .find(|&&k| images[k].distance(image) <= threshold)
+        {
+            Some(&k) => near_duplicates.push((i, k)),
+            None => kept.push(i),
+        }
+    }
+    near_duplicates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageFormat, Rgb, RgbImage};
+
+    /// Smooth test pattern, mirrored when `flip` is set
+    fn pattern(width: u32, height: u32, flip: bool) -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_fn(width, height, |x, y| {
+            let x = if flip { width - 1 - x } else { x };
+            let v = ((x * 255 / width) + (y * 128 / height)) as u8;
+            Rgb([v, v / 2, 255 - v])
+        }))
+    }
+
+    fn fingerprint(hash: u64, width: u32, height: u32) -> ImageFingerprint {
+        ImageFingerprint {
+            hash,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn test_resaved_copy_is_near() {
+        let dir = tempfile::tempdir().unwrap();
+        let original = dir.path().join("original.png");
+        let resized = dir.path().join("resized.jpg");
+        let other = dir.path().join("other.webp");
+        pattern(400, 300, false)
+            .save_with_format(&original, ImageFormat::Png)
+            .unwrap();
+        pattern(200, 150, false)
+            .save_with_format(&resized, ImageFormat::Jpeg)
+            .unwrap();
+        pattern(400, 300, true)
+            .save_with_format(&other, ImageFormat::WebP)
+            .unwrap();
+
+        let original = compute_fingerprint(&original).unwrap();
+        let resized = compute_fingerprint(&resized).unwrap();
+        let other = compute_fingerprint(&other).unwrap();
+        assert_eq!((original.width, original.height), (400, 300));
+        assert_eq!(resized.resolution(), 200 * 150);
+        assert!(original.distance(&resized) <= 4);
+        assert!(original.distance(&other) > 16);
+    }
+
+    #[test]
+    fn test_undecodable_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("broken.jpg");
+        std::fs::write(&path, b"not a jpeg").unwrap();
+        assert!(matches!(
+            compute_fingerprint(&path),
+            Err(Error::ImageDecode { .. })
+        ));
+        assert!(is_decodable(&path));
+        assert!(is_decodable(Path::new("a.WEBP")));
+        assert!(!is_decodable(Path::new("a.heic")));
+    }
+
+    #[test]
+    fn test_find_near_duplicates() {
+        let images = [
+            fingerprint(0b0000, 400, 300),
+            fingerprint(0b0011, 200, 150),
+            fingerprint(0b1111, 200, 150),
+            fingerprint(0b0111, 100, 75),
+        ];
+        // The last image is within reach of the second, but that one is not kept
+        assert_eq!(find_near_duplicates(&images, 2), [(1, 0), (3, 2)]);
+        assert_eq!(find_near_duplicates(&images, 0), []);
+    }
+}
@@ -6,13 +6,17 @@
 //! - Computing hashes for deduplication
 //! - Organizing files to output directory
 
+use crate::cache::{HashCache, MetadataCache};
 use crate::config::{
     ClassificationRule, Config, FileOperation, FileType, MonthFormat, ProcessingMode,
 };
 use crate::error::{Error, Result};
-use crate::hash::{compute_file_hash, compute_metadata_hash};
+use crate::hash::{compute_metadata_hash, files_identical};
+use crate::perceptual::{self, ImageFingerprint};
 use crate::state::{IncrementalWatermark, ProcessingState};
-use crate::time::{ExtractedTime, extract_time};
+use crate::time::audit::{self, TimeAudit};
+use crate::time::filename::recognize_app;
+use crate::time::{ExtractedTime, TimePrecision, TimeSource, sequence, xmp};
 use chrono::{Datelike, NaiveDateTime};
 
 use rayon::prelude::*;
@@ -90,6 +94,8 @@ pub enum ProcessingStatus {
     Skipped,
     /// File was skipped as duplicate
     Duplicate,
+    /// Image was skipped as a re-saved or resized copy of a larger image
+    NearDuplicate,
     /// Processing failed
     Failed,
     /// Dry run - would have processed
@@ -103,7 +109,13 @@ pub struct ProcessingStats {
     pub processed: AtomicUsize,
     pub skipped: AtomicUsize,
     pub duplicates: AtomicUsize,
+    /// Images skipped as near duplicates of a larger image
+    pub near_duplicates: AtomicUsize,
     pub failed: AtomicUsize,
+    /// Files whose time got a camera clock correction
+    pub clock_corrected: AtomicUsize,
+    /// Files whose time was interpolated from their camera sequence
+    pub interpolated: AtomicUsize,
 }
 
 impl Clone for ProcessingStats {
@@ -113,7 +125,10 @@ impl Clone for ProcessingStats {
             processed: AtomicUsize::new(self.processed.load(Ordering::Relaxed)),
             skipped: AtomicUsize::new(self.skipped.load(Ordering::Relaxed)),
             duplicates: AtomicUsize::new(self.duplicates.load(Ordering::Relaxed)),
+            near_duplicates: AtomicUsize::new(self.near_duplicates.load(Ordering::Relaxed)),
             failed: AtomicUsize::new(self.failed.load(Ordering::Relaxed)),
+            clock_corrected: AtomicUsize::new(self.clock_corrected.load(Ordering::Relaxed)),
+            interpolated: AtomicUsize::new(self.interpolated.load(Ordering::Relaxed)),
         }
     }
 }
@@ -125,12 +140,15 @@ impl ProcessingStats {
 
     pub fn summary(&self) -> String {
         format!(
-            "Total: {}, Processed: {}, Skipped: {}, Duplicates: {}, Failed: {}",
+            "Total: {}, Processed: {}, Skipped: {}, Duplicates: {}, Near duplicates: {}, Failed: {}, Clock corrected: {}, Interpolated: {}",
             self.total_files.load(Ordering::Relaxed),
             self.processed.load(Ordering::Relaxed),
             self.skipped.load(Ordering::Relaxed),
             self.duplicates.load(Ordering::Relaxed),
-            self.failed.load(Ordering::Relaxed)
+            self.near_duplicates.load(Ordering::Relaxed),
+            self.failed.load(Ordering::Relaxed),
+            self.clock_corrected.load(Ordering::Relaxed),
+            self.interpolated.load(Ordering::Relaxed)
         )
     }
 }
@@ -139,8 +157,11 @@ impl ProcessingStats {
 pub struct Processor {
     config: Config,
     state: ProcessingState,
+    metadata_cache: MetadataCache,
+    hash_cache: HashCache,
     watermark: Option<IncrementalWatermark>,
     stats: Arc<ProcessingStats>,
+    time_conflicts: Option<usize>,
 }
 
 impl Processor {
@@ -168,6 +189,13 @@ impl Processor {
             }
         }
 
+        // Reject unusable patterns instead of silently never matching them
+        for pattern in &config.filename_patterns {
+            pattern.validate().map_err(|reason| {
+                Error::Config(format!("Filename pattern '{}': {}", pattern.name, reason))
+            })?;
+        }
+
         // Load existing state for incremental processing
         let state = if config.processing_mode == ProcessingMode::Incremental {
             ProcessingState::load(&config.get_state_file())?
@@ -175,6 +203,16 @@ impl Processor {
             ProcessingState::new()
         };
 
+        // Extracted times of earlier runs are reused in incremental mode
+        let metadata_cache = if config.processing_mode == ProcessingMode::Incremental {
+            MetadataCache::load(&config.get_metadata_cache_file(), &config)
+        } else {
+            MetadataCache::new(&config)
+        };
+
+        // Content hashes of earlier runs are reused in every mode
+        let hash_cache = HashCache::load(&config.get_hash_cache_file(), &config);
+
         // Helper to collect all supported extensions
         let get_extensions = || -> Vec<String> {
             config
@@ -240,8 +278,11 @@ impl Processor {
         Ok(Self {
             config,
             state,
+            metadata_cache,
+            hash_cache,
             watermark,
             stats: Arc::new(ProcessingStats::new()),
+            time_conflicts: None,
         })
     }
 
@@ -276,6 +317,33 @@ impl Processor {
 
         let config = Arc::new(self.config.clone());
 
+        // Sequence interpolation: date files that only have a file system time
+        // from the dated files around them in the same camera sequence
+        let interpolated: HashMap<PathBuf, ExtractedTime> = if config.interpolate_sequences {
+            info!("Interpolating times from camera sequence numbers...");
+            let times: Vec<_> = files
+                .par_iter()
+                .map(|path| {
+                    let time = self.metadata_cache.extract_time(path, &config).ok();
+                    (path.clone(), time)
+                })
+                .collect();
+            let interpolated = sequence::interpolate(&times);
+            info!(count = interpolated.len(), "Interpolated file times");
+            interpolated
+        } else {
+            HashMap::new()
+        };
+
+        let mut lookups = FileLookups {
+            config: &config,
+            interpolated,
+            times: &self.metadata_cache,
+            hashes: &self.hash_cache,
+            sidecar_owners: HashMap::new(),
+            shared_sidecar_times: HashMap::new(),
+        };
+
         // Incremental mode: Filter files by timestamp using watermark
         // This is done BEFORE computing hashes to minimize disk I/O
         let (files, skipped_by_watermark) = if config.processing_mode == ProcessingMode::Incremental
@@ -291,17 +359,20 @@ impl Processor {
 
                 for file_path in files {
                     // Extract timestamp for comparison
-                    match extract_time(&file_path, &config) {
-                        Ok(time_info) => {
-                            if watermark.is_newer(&time_info.timestamp) {
+                    let time_info = lookups.time(&file_path);
+                    // Compare the latest time the file may have: a date-only
+                    // time could be any time that day
+                    match time_info.as_ref().map(ExtractedTime::latest_possible) {
+                        Ok(Some(latest)) => {
+                            if watermark.is_newer(&latest) {
                                 newer_files.push(file_path);
                             } else {
                                 debug!(?file_path, "Skipping file older than watermark");
                                 skipped_count += 1;
                             }
                         }
-                        Err(_) => {
-                            // Can't determine timestamp, include for processing
+                        Ok(None) | Err(_) => {
+                            // Guessed or unknown timestamp, include for processing
                             newer_files.push(file_path);
                         }
                     }
@@ -334,72 +405,142 @@ impl Processor {
             return Ok(Vec::new());
         }
 
-        // Phase 1: Compute hashes for all files in parallel to determine duplicates
-        info!("Computing file hashes for deduplication...");
-        let file_hashes: Vec<(PathBuf, Option<u64>)> = if config.deduplicate {
-            files
+        // Audit mode: run every time source and report disagreements
+        if config.audit {
+            info!("Auditing time sources...");
+            let audits: Vec<TimeAudit> = files
                 .par_iter()
-                .map(|path| {
-                    let hash = compute_file_hash(path, config.large_file_threshold).ok();
-                    (path.clone(), hash)
-                })
-                .collect()
-        } else {
-            files.iter().map(|p| (p.clone(), None)).collect()
-        };
+                .map(|path| TimeAudit::run(path, &config))
+                .collect();
+            let conflicts = audits.iter().filter(|a| a.conflict).count();
+            let report_path = config.get_audit_report_file();
+            audit::write_report(&report_path, &audits, &config)?;
+            info!(
+                report = %report_path.display(),
+                files = audits.len(),
+                conflicts,
+                "Wrote time audit report"
+            );
+            self.time_conflicts = Some(conflicts);
+        }
+
+        // Phase 1: Compute hashes of possible duplicates in parallel
+        // Only files of the same size can be identical, so files whose size
+        // is unique among the inputs (and the target in Supplement mode) are
+        // never read here
+        let (file_hashes, existing_hashes): (HashMap<PathBuf, u128>, HashMap<u128, PathBuf>) =
+            if config.deduplicate {
+                info!("Grouping files by size for deduplication...");
+                let files_by_size = group_by_size(&files);
+
+                // For Supplement mode: hash target files that share a size with an input
+                let (existing_hashes, target_sizes) =
+                    if config.processing_mode == ProcessingMode::Supplement {
+                        info!("Scanning target directory for existing files...");
+                        self.scan_target_hashes(&files_by_size)?
+                    } else {
+                        (HashMap::new(), HashSet::new())
+                    };
+
+                let candidates: Vec<&PathBuf> = files_by_size
+                    .iter()
+                    .filter(|(size, group)| group.len() > 1 || target_sizes.contains(*size))
+                    .flat_map(|(_, group)| group)
+                    .collect();
+                info!(
+                    candidates = candidates.len(),
+                    "Computing file hashes for deduplication..."
+                );
+                let file_hashes = candidates
+                    .par_iter()
+                    .filter_map(|path| {
+                        let hash = lookups.hash(path).ok()?;
+                        Some(((*path).clone(), hash))
+                    })
+                    .collect();
+                (file_hashes, existing_hashes)
+            } else {
+                (HashMap::new(), HashMap::new())
+            };
 
         // Phase 2: Select best file for each unique hash (cleanest filename wins)
         // Group files by hash
-        let mut hash_groups: HashMap<u64, Vec<PathBuf>> = HashMap::new();
-        let mut no_hash_files: Vec<PathBuf> = Vec::new();
+        let mut hash_groups: HashMap<u128, Vec<PathBuf>> = HashMap::new();
 
-        for (path, hash) in &file_hashes {
-            if let Some(h) = hash {
+        for path in &files {
+            if let Some(h) = file_hashes.get(path) {
                 hash_groups.entry(*h).or_default().push(path.clone());
-            } else {
-                no_hash_files.push(path.clone());
             }
         }
 
-        // Select the best file from each group (files are already sorted by priority)
-        let mut files_to_process: HashSet<PathBuf> = HashSet::new();
-        let mut hash_to_best_file: HashMap<u64, PathBuf> = HashMap::new();
+        // Match each duplicate to the best identical file of its group. Equal
+        // hashes only nominate candidates, the contents are compared before
+        // any file is dropped
+        let duplicate_of: HashMap<PathBuf, PathBuf> = hash_groups
+            .into_par_iter()
+            .filter(|(_, group)| group.len() > 1)
+            .flat_map_iter(|(_, mut group)| {
+                // Sort by priority score (lowest = best)
+                group.sort_by_cached_key(|p| filename_priority_score(p));
+                confirm_duplicates(group)
+            })
+            .collect();
 
-        for (hash, mut group) in hash_groups {
-            // Sort by priority score (lowest = best)
-            group.sort_by_cached_key(|p| filename_priority_score(p));
-            let best = group.remove(0);
-            hash_to_best_file.insert(hash, best.clone());
-            files_to_process.insert(best);
-        }
+        // Phase 2b: Cluster the remaining images by perceptual hash, keeping
+        // the highest resolution image of each cluster (cleanest filename on ties)
+        let near_duplicate_of: HashMap<PathBuf, PathBuf> = if config.near_duplicates {
+            info!("Computing perceptual hashes for near-duplicate detection...");
+            let mut images: Vec<(&PathBuf, ImageFingerprint)> = files
+                .par_iter()
+                .filter(|path| !duplicate_of.contains_key(*path) && perceptual::is_decodable(path))
+                .filter_map(|path| match perceptual::compute_fingerprint(path) {
+                    Ok(fingerprint) => Some((path, fingerprint)),
+                    Err(e) => {
+                        debug!(?path, error = %e, "Failed to compute perceptual hash");
+                        None
+                    }
+                })
+                .collect();
+            // Stable sort, files are already in filename priority order
+            images.sort_by_key(|(_, fingerprint)| std::cmp::Reverse(fingerprint.resolution()));
 
-        // All files without hash should be processed
-        for path in no_hash_files {
-            files_to_process.insert(path);
-        }
+            let fingerprints: Vec<_> = images.iter().map(|(_, f)| *f).collect();
+            perceptual::find_near_duplicates(&fingerprints, config.near_duplicate_threshold)
+                .into_iter()
+                .map(|(near, kept)| (images[near].0.clone(), images[kept].0.clone()))
+                .collect()
+        } else {
+            HashMap::new()
+        };
 
-        let duplicate_count = files.len() - files_to_process.len();
+        let duplicate_count = duplicate_of.len() + near_duplicate_of.len();
         info!(
-            "Selected {} files to process ({} duplicates will be skipped)",
-            files_to_process.len(),
-            duplicate_count
+            "Selected {} files to process ({} duplicates and {} near duplicates will be skipped)",
+            files.len() - duplicate_count,
+            duplicate_of.len(),
+            near_duplicate_of.len()
         );
 
-        // For Supplement mode: scan target directory for existing file hashes
-        let existing_hashes: HashSet<u64> = if config.processing_mode == ProcessingMode::Supplement
-        {
-            info!("Scanning target directory for existing files...");
-            self.scan_target_hashes()?
-        } else {
-            HashSet::new()
-        };
-
-        if config.processing_mode == ProcessingMode::Supplement && !existing_hashes.is_empty() {
-            info!(
-                "Found {} existing files in target directory",
-                existing_hashes.len()
-            );
+        // Stem sidecars shared by a RAW+JPEG pair go along with one file only
+        let kept_files: Vec<&PathBuf> = files
+            .iter()
+            .filter(|path| {
+                !duplicate_of.contains_key(*path) && !near_duplicate_of.contains_key(*path)
+            })
+            .collect();
+        let shared_sidecars = shared_sidecars(&kept_files, &config);
+        if config.operation == FileOperation::Move && !config.dry_run {
+            // Read the times of the sharing files before the owner moves the sidecar away
+            let sharers: Vec<&PathBuf> = shared_sidecars.values().flatten().collect();
+            lookups.shared_sidecar_times = sharers
+                .par_iter()
+                .filter_map(|path| Some(((*path).clone(), lookups.time(path).ok()?)))
+                .collect();
         }
+        lookups.sidecar_owners = shared_sidecars
+            .into_iter()
+            .map(|(sidecar, sharers)| (sidecar, sharers[0].clone()))
+            .collect();
 
         // Phase 3: Process files
         info!("Processing files...");
@@ -409,12 +550,10 @@ impl Processor {
         // Use self.stats to share with UI progress
         let stats = self.stats.clone();
 
-        // Map to track hash -> destination for duplicate reporting
-        let hash_to_dest: Arc<Mutex<HashMap<u64, PathBuf>>> = Arc::new(Mutex::new(HashMap::new()));
+        // Map to track source -> destination for duplicate reporting
+        let source_to_dest: Arc<Mutex<HashMap<PathBuf, PathBuf>>> =
+            Arc::new(Mutex::new(HashMap::new()));
 
-        // Convert file_hashes to a lookup map
-        let file_hash_map: HashMap<PathBuf, Option<u64>> = file_hashes.into_iter().collect();
-        let hash_to_best_file = Arc::new(hash_to_best_file);
         let existing_hashes = Arc::new(existing_hashes);
 
         // Process all files, marking duplicates appropriately
@@ -422,32 +561,67 @@ impl Processor {
             .par_iter()
             .map(|file_path| {
                 let _file_span = span!(Level::DEBUG, "process_file", ?file_path).entered();
+                let content_hash = file_hashes.get(file_path).copied();
 
                 // Check if this is a duplicate that should be skipped
-                if !files_to_process.contains(file_path) {
-                    // Find the hash for this file to get the kept file's destination
-                    if let Some(Some(hash)) = file_hash_map.get(file_path) {
-                        // Get destination from already-processed best file, or report the best file path
-                        let dest = {
-                            let dest_map = hash_to_dest.lock().unwrap();
-                            dest_map.get(hash).cloned()
-                        }
-                        .or_else(|| hash_to_best_file.get(hash).cloned());
+                let duplicate = duplicate_of
+                    .get(file_path)
+                    .map(|best| (best, ProcessingStatus::Duplicate))
+                    .or_else(|| {
+                        near_duplicate_of
+                            .get(file_path)
+                            .map(|best| (best, ProcessingStatus::NearDuplicate))
+                    });
+                if let Some((best, status)) = duplicate {
+                    // Get destination from already-processed best file, or report the best file path
+                    let dest = {
+                        let dest_map = source_to_dest.lock().unwrap();
+                        dest_map.get(best).cloned()
+                    }
+                    .unwrap_or_else(|| best.clone());
 
+                    if status == ProcessingStatus::Duplicate {
                         debug!(
                             ?file_path,
                             ?dest,
                             "Skipping duplicate file (inferior filename)"
                         );
                         stats.duplicates.fetch_add(1, Ordering::Relaxed);
-                        return FileResult {
-                            source: file_path.clone(),
-                            destination: dest,
-                            time_info: None,
-                            status: ProcessingStatus::Duplicate,
-                            error: None,
-                        };
+                    } else {
+                        debug!(
+                            ?file_path,
+                            ?dest,
+                            "Skipping near-duplicate image (lower resolution)"
+                        );
+                        stats.near_duplicates.fetch_add(1, Ordering::Relaxed);
                     }
+                    return FileResult {
+                        source: file_path.clone(),
+                        destination: Some(dest),
+                        time_info: None,
+                        status,
+                        error: None,
+                    };
+                }
+
+                // Supplement mode: skip if the file already exists in target directory
+                if config.processing_mode == ProcessingMode::Supplement
+                    && let Some(hash) = content_hash
+                    && let Some(existing) = existing_hashes.get(&hash)
+                    && is_identical(file_path, existing)
+                {
+                    debug!(
+                        ?file_path,
+                        "File already exists in target (Supplement mode), skipping"
+                    );
+                    stats.skipped.fetch_add(1, Ordering::Relaxed);
+                    return FileResult {
+                        source: file_path.clone(),
+                        destination: None,
+                        time_info: None,
+                        status: ProcessingStatus::Skipped,
+                        error: None,
+                    };
                 }
 
                 process_single_file(
@@ -455,9 +629,9 @@ impl Processor {
                     &config,
                     &state,
                     &stats,
-                    &hash_to_dest,
-                    &file_hash_map,
-                    &existing_hashes,
+                    &source_to_dest,
+                    content_hash,
+                    &lookups,
                 )
             })
             .collect();
@@ -472,10 +646,24 @@ impl Processor {
         if self.config.processing_mode == ProcessingMode::Incremental && !self.config.dry_run {
             self.state.save(&self.config.get_state_file())?;
 
+            // The caches only save work, a failure to write them is not fatal
+            if let Err(e) = self
+                .metadata_cache
+                .save(&self.config.get_metadata_cache_file())
+            {
+                warn!(error = %e, "Failed to save metadata cache");
+            }
+
             // Update watermark with newest processed file
             self.update_watermark(&results)?;
         }
 
+        if !self.config.dry_run
+            && let Err(e) = self.hash_cache.save(&self.config.get_hash_cache_file())
+        {
+            warn!(error = %e, "Failed to save hash cache");
+        }
+
         // Log summary
         info!("{}", self.stats.summary());
 
@@ -554,11 +742,17 @@ impl Processor {
     }
 
     /// Scan target directory for existing file hashes (for Supplement mode)
-    fn scan_target_hashes(&self) -> Result<HashSet<u64>> {
-        let mut hashes = HashSet::new();
+    ///
+    /// Only target files sharing their size with an input file are hashed.
+    /// Returns the target file for each hash and the sizes that were matched.
+    fn scan_target_hashes(
+        &self,
+        input_sizes: &HashMap<u64, Vec<PathBuf>>,
+    ) -> Result<(HashMap<u128, PathBuf>, HashSet<u64>)> {
+        let mut hashes = HashMap::new();
 
         if !self.config.output_dir.exists() {
-            return Ok(hashes);
+            return Ok((hashes, HashSet::new()));
         }
 
         let files: Vec<PathBuf> = WalkDir::new(&self.config.output_dir)
@@ -576,23 +770,40 @@ impl Processor {
             .map(|e| e.path().to_path_buf())
             .collect();
 
+        // Keep the files an input file could be identical to
+        let mut sizes = HashSet::new();
+        let mut candidates = Vec::new();
+        for (size, group) in group_by_size(&files) {
+            if input_sizes.contains_key(&size) {
+                sizes.insert(size);
+                candidates.extend(group);
+            }
+        }
+        info!(
+            "Found {} existing files in target directory ({} share a size with an input file)",
+            files.len(),
+            candidates.len()
+        );
+
         // Compute hashes in parallel
-        let computed_hashes: Vec<Option<u64>> = files
+        let computed_hashes: Vec<Option<u128>> = candidates
             .par_iter()
-            .map(|path| compute_file_hash(path, self.config.large_file_threshold).ok())
+            .map(|path| self.hash_cache.compute_file_hash(path).ok())
             .collect();
 
-        for hash in computed_hashes.into_iter().flatten() {
-            hashes.insert(hash);
+        for (path, hash) in candidates.into_iter().zip(computed_hashes) {
+            if let Some(hash) = hash {
+                hashes.entry(hash).or_insert(path);
+            }
         }
 
-        Ok(hashes)
+        Ok((hashes, sizes))
     }
 
     /// Update watermark with the newest successfully processed file
     fn update_watermark(&mut self, results: &[FileResult]) -> Result<()> {
         // Find the newest successfully processed file
-        let mut newest: Option<(PathBuf, NaiveDateTime, u64)> = None;
+        let mut newest: Option<(PathBuf, NaiveDateTime, u128)> = None;
 
         for result in results {
             // Only consider successfully processed files
@@ -603,6 +814,11 @@ impl Processor {
             }
 
             if let (Some(time_info), Some(dest)) = (&result.time_info, &result.destination) {
+                // A guessed time must not push the watermark past real ones
+                if time_info.precision == TimePrecision::Guessed {
+                    continue;
+                }
+
                 let is_newer = match &newest {
                     Some((_, ts, _)) => time_info.timestamp > *ts,
                     None => true,
@@ -616,8 +832,7 @@ impl Processor {
                         .to_path_buf();
 
                     // Compute hash if needed
-                    let hash =
-                        compute_file_hash(dest, self.config.large_file_threshold).unwrap_or(0);
+                    let hash = self.hash_cache.compute_file_hash(dest).unwrap_or(0);
 
                     newest = Some((relative_path, time_info.timestamp, hash));
                 }
@@ -658,44 +873,71 @@ impl Processor {
         &self.stats
     }
 
+    /// Number of files with conflicting time sources, if the audit ran
+    pub fn time_conflicts(&self) -> Option<usize> {
+        self.time_conflicts
+    }
+
     /// Get a clone of the internal stats Arc for shared access
     pub fn stats_arc(&self) -> Arc<ProcessingStats> {
         self.stats.clone()
     }
 }
 
+/// Cached and interpolated per-file information shared by a run
+struct FileLookups<'a> {
+    config: &'a Config,
+    /// Times interpolated from camera sequences
+    interpolated: HashMap<PathBuf, ExtractedTime>,
+    times: &'a MetadataCache,
+    hashes: &'a HashCache,
+    /// Files that take a shared stem sidecar along, by sidecar
+    sidecar_owners: HashMap<PathBuf, PathBuf>,
+    /// Times of files sharing a sidecar, read before the owner moves it away
+    shared_sidecar_times: HashMap<PathBuf, ExtractedTime>,
+}
+
+impl FileLookups<'_> {
+    /// Time of a file: interpolated, read ahead, cached or freshly extracted
+    fn time(&self, path: &Path) -> Result<ExtractedTime> {
+        match self
+            .interpolated
+            .get(path)
+            .or_else(|| self.shared_sidecar_times.get(path))
+        {
+            Some(time_info) => Ok(time_info.clone()),
+            None => self.times.extract_time(path, self.config),
+        }
+    }
+
+    /// Content hash of a file, cached while the file is unchanged
+    fn hash(&self, path: &Path) -> Result<u128> {
+        self.hashes.compute_file_hash(path)
+    }
+
+    /// Sidecars that go along with a file, leaving out shared ones owned by a sibling
+    fn sidecars(&self, path: &Path) -> Vec<PathBuf> {
+        xmp::find_sidecars(path)
+            .into_iter()
+            .filter(|sidecar| {
+                self.sidecar_owners
+                    .get(sidecar)
+                    .is_none_or(|owner| owner == path)
+            })
+            .collect()
+    }
+}
+
 /// Process a single file (standalone function for parallel processing)
 fn process_single_file(
     path: &Path,
     config: &Arc<Config>,
     state: &Arc<Mutex<ProcessingState>>,
     stats: &Arc<ProcessingStats>,
-    hash_to_dest: &Arc<Mutex<HashMap<u64, PathBuf>>>,
-    file_hash_map: &HashMap<PathBuf, Option<u64>>,
-    existing_hashes: &Arc<HashSet<u64>>,
+    source_to_dest: &Arc<Mutex<HashMap<PathBuf, PathBuf>>>,
+    content_hash: Option<u128>,
+    lookups: &FileLookups,
 ) -> FileResult {
-    // Get content hash from pre-computed map (needed for Supplement mode check)
-    let content_hash = file_hash_map.get(&path.to_path_buf()).and_then(|h| *h);
-
-    // Supplement mode: skip if file hash already exists in target directory
-    if config.processing_mode == ProcessingMode::Supplement
-        && let Some(hash) = content_hash
-        && existing_hashes.contains(&hash)
-    {
-        debug!(
-            ?path,
-            "File already exists in target (Supplement mode), skipping"
-        );
-        stats.skipped.fetch_add(1, Ordering::Relaxed);
-        return FileResult {
-            source: path.to_path_buf(),
-            destination: None,
-            time_info: None,
-            status: ProcessingStatus::Skipped,
-            error: None,
-        };
-    }
-
     // Check if file needs processing (incremental mode)
     if config.processing_mode == ProcessingMode::Incremental {
         match compute_metadata_hash(path) {
@@ -719,9 +961,26 @@ fn process_single_file(
         }
     }
 
-    // Extract time information
-    let time_info = match extract_time(path, config) {
-        Ok(info) => info,
+    // Files with a unique size were not hashed for deduplication, but the
+    // state records the hash of every processed file
+    let content_hash = match content_hash {
+        None if config.deduplicate && config.processing_mode == ProcessingMode::Incremental => {
+            lookups.hash(path).ok()
+        }
+        hash => hash,
+    };
+
+    // Extract time information, unless it was interpolated or cached
+    let time_info = match lookups.time(path) {
+        Ok(info) => {
+            if info.clock_correction.is_some() {
+                stats.clock_corrected.fetch_add(1, Ordering::Relaxed);
+            }
+            if info.source == TimeSource::Interpolated {
+                stats.interpolated.fetch_add(1, Ordering::Relaxed);
+            }
+            info
+        }
         Err(e) => {
             error!(?path, error = %e, "Failed to extract time");
             stats.failed.fetch_add(1, Ordering::Relaxed);
@@ -739,13 +998,16 @@ fn process_single_file(
     if let Some(hash) = content_hash
         && config.processing_mode == ProcessingMode::Incremental
     {
-        let state_guard = state.lock().unwrap();
-        if let Some(existing) = state_guard.has_content_hash(hash) {
+        let existing = state.lock().unwrap().has_content_hash(hash).cloned();
+        // Confirm against the recorded copy; if it is gone the file is processed
+        if let Some(existing) = existing
+            && is_identical(path, &existing)
+        {
             debug!(?path, ?existing, "Duplicate file detected (from state)");
             stats.duplicates.fetch_add(1, Ordering::Relaxed);
             return FileResult {
                 source: path.to_path_buf(),
-                destination: Some(existing.clone()),
+                destination: Some(existing),
                 time_info: Some(time_info),
                 status: ProcessingStatus::Duplicate,
                 error: None,
@@ -774,9 +1036,16 @@ fn process_single_file(
     // - Full mode: overwrite (use base path, don't add suffix)
     // - Supplement/Incremental mode: skip if same content already exists
     let dest_path = if base_dest_path.exists() {
-        if let Some(source_hash) = content_hash {
-            if let Ok(dest_hash) = compute_file_hash(&base_dest_path, config.large_file_threshold) {
-                if source_hash == dest_hash {
+        // Files of different size can't be identical, and a file with a
+        // unique size among the inputs has not been hashed yet
+        let source_hash = if config.deduplicate && same_size(path, &base_dest_path) {
+            content_hash.or_else(|| lookups.hash(path).ok())
+        } else {
+            None
+        };
+        if let Some(source_hash) = source_hash {
+            if let Ok(dest_hash) = lookups.hash(&base_dest_path) {
+                if source_hash == dest_hash && is_identical(path, &base_dest_path) {
                     match config.processing_mode {
                         ProcessingMode::Full => {
                             // Full mode: file is identical, still "process" it
@@ -898,9 +1167,9 @@ fn process_single_file(
         );
 
         // Record destination for duplicate reporting
-        if let Some(hash) = content_hash {
-            let mut dest_map = hash_to_dest.lock().unwrap();
-            dest_map.insert(hash, dest_path.clone());
+        {
+            let mut dest_map = source_to_dest.lock().unwrap();
+            dest_map.insert(path.to_path_buf(), dest_path.clone());
         }
 
         stats.processed.fetch_add(1, Ordering::Relaxed);
@@ -914,7 +1183,8 @@ fn process_single_file(
     }
 
     // Perform the file operation
-    if let Err(e) = perform_file_operation(path, &dest_path, config) {
+    let sidecars = lookups.sidecars(path);
+    if let Err(e) = perform_file_operation(path, &dest_path, &sidecars, config) {
         error!(?path, ?dest_path, error = %e, "Failed to process file");
         stats.failed.fetch_add(1, Ordering::Relaxed);
         return FileResult {
@@ -927,9 +1197,9 @@ fn process_single_file(
     }
 
     // Record destination for duplicate reporting
-    if let Some(hash) = content_hash {
-        let mut dest_map = hash_to_dest.lock().unwrap();
-        dest_map.insert(hash, dest_path.clone());
+    {
+        let mut dest_map = source_to_dest.lock().unwrap();
+        dest_map.insert(path.to_path_buf(), dest_path.clone());
     }
 
     // Update state
@@ -963,6 +1233,62 @@ fn process_single_file(
     }
 }
 
+/// Match each file of a hash group to an identical file kept before it
+///
+/// `group` is sorted best first. Returns `(duplicate, kept file)` pairs; a
+/// file that matches the hash but not the content of any kept file is kept
+/// as well.
+fn confirm_duplicates(group: Vec<PathBuf>) -> Vec<(PathBuf, PathBuf)> {
+    let mut kept: Vec<PathBuf> = Vec::new();
+    let mut duplicates = Vec::new();
+
+    for path in group {
+        match kept.iter().find(|best| is_identical(&path, best)) {
+            Some(best) => duplicates.push((path, best.clone())),
+            None => {
+                if !kept.is_empty() {
+                    warn!(
+                        ?path,
+                        "File has the hash of another file but different content"
+                    );
+                }
+                kept.push(path);
+            }
+        }
+    }
+    duplicates
+}
+
+/// Whether two files have identical content; errors count as different
+fn is_identical(a: &Path, b: &Path) -> bool {
+    files_identical(a, b).unwrap_or_else(|e| {
+        warn!(?a, ?b, error = %e, "Failed to compare files");
+        false
+    })
+}
+
+/// Whether two files have the same size
+fn same_size(a: &Path, b: &Path) -> bool {
+    match (fs::metadata(a), fs::metadata(b)) {
+        (Ok(a), Ok(b)) => a.len() == b.len(),
+        _ => false,
+    }
+}
+
+/// Group files by size, leaving out files whose size can't be read
+fn group_by_size(files: &[PathBuf]) -> HashMap<u64, Vec<PathBuf>> {
+    let sizes: Vec<(u64, PathBuf)> = files
+        .par_iter()
+        .filter_map(|path| Some((fs::metadata(path).ok()?.len(), path.clone())))
+        .collect();
+
+    let mut groups: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+    for (size, path) in sizes {
+        groups.entry(size).or_default().push(path);
+    }
+    groups
+}
+
 /// Build the base destination path based on classification rules (without conflict resolution)
 fn build_base_destination_path(
     source: &Path,
@@ -1014,6 +1340,13 @@ fn build_base_destination_path(
         }
     }
 
+    // Source app classification (after file type classification)
+    if config.classify_by_app
+        && let Some(app_match) = filename.to_str().and_then(recognize_app)
+    {
+        dest.push(app_match.app.folder_name());
+    }
+
     dest.push(filename);
     Ok(dest)
 }
@@ -1050,13 +1383,95 @@ fn resolve_filename_conflict(mut path: PathBuf) -> Result<PathBuf> {
 }
 
 /// Perform the actual file operation (copy, move, symlink, hardlink)
-fn perform_file_operation(source: &Path, dest: &Path, config: &Config) -> Result<()> {
+///
+/// The given XMP sidecars of the file go along to the same destination name,
+/// replacing any sidecar already there just like the file itself.
+fn perform_file_operation(
+    source: &Path,
+    dest: &Path,
+    sidecars: &[PathBuf],
+    config: &Config,
+) -> Result<()> {
     // Create parent directory
     if let Some(parent) = dest.parent() {
         fs::create_dir_all(parent)?;
     }
 
-    match config.operation {
+    transfer_file(source, dest, config.operation)?;
+
+    for sidecar in sidecars {
+        let Some(sidecar_dest) = sidecar_destination(source, sidecar, dest) else {
+            continue;
+        };
+        // The sidecar describes the file that was just written, not the old one
+        if fs::symlink_metadata(&sidecar_dest).is_ok() {
+            debug!(?sidecar_dest, "Replacing existing sidecar");
+            if let Err(e) = fs::remove_file(&sidecar_dest) {
+                warn!(?sidecar, ?sidecar_dest, error = %e, "Failed to replace sidecar");
+                continue;
+            }
+        }
+        match transfer_file(sidecar, &sidecar_dest, config.operation) {
+            Ok(()) => debug!(?sidecar, ?sidecar_dest, "Transferred sidecar"),
+            Err(e) => warn!(?sidecar, error = %e, "Failed to transfer sidecar"),
+        }
+    }
+
+    Ok(())
+}
+
+/// Stem sidecars shared by several files, with the sharing files owner first
+///
+/// `DSC_0001.xmp` belongs to both `DSC_0001.ARW` and `DSC_0001.JPG`, but
+/// only one of them can take it along. The RAW file owns it, else the first
+/// file by path, so the outcome doesn't depend on processing order.
+fn shared_sidecars(files: &[&PathBuf], config: &Config) -> HashMap<PathBuf, Vec<PathBuf>> {
+    let stem_sidecars: Vec<(PathBuf, PathBuf)> = files
+        .par_iter()
+        .flat_map_iter(|path| {
+            xmp::find_sidecars(path)
+                .into_iter()
+                .filter(|sidecar| xmp::is_stem_sidecar(path, sidecar))
+                .map(|sidecar| (sidecar, (*path).clone()))
+        })
+        .collect();
+
+    let mut sharers: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
+    for (sidecar, path) in stem_sidecars {
+        sharers.entry(sidecar).or_default().push(path);
+    }
+    sharers.retain(|_, sharers| sharers.len() > 1);
+
+    let is_raw = |path: &Path| {
+        path.extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|ext| config.is_raw(ext))
+    };
+    for sharers in sharers.values_mut() {
+        sharers.sort_by(|a, b| is_raw(b).cmp(&is_raw(a)).then(a.cmp(b)));
+    }
+    sharers
+}
+
+/// Destination of a sidecar whose primary file goes to `dest`
+///
+/// `DSC_0001.ARW.xmp` follows the full filename and `DSC_0001.xmp` the stem,
+/// so a conflict suffix on the primary carries over to the sidecar.
+fn sidecar_destination(source: &Path, sidecar: &Path, dest: &Path) -> Option<PathBuf> {
+    let sidecar_name = sidecar.file_name()?.to_str()?;
+    let (base, suffix) = match sidecar_name.strip_prefix(source.file_name()?.to_str()?) {
+        Some(suffix) => (dest.file_name()?, suffix),
+        None => (
+            dest.file_stem()?,
+            sidecar_name.strip_prefix(source.file_stem()?.to_str()?)?,
+        ),
+    };
+    Some(dest.with_file_name(format!("{}{suffix}", base.to_str()?)))
+}
+
+/// Copy, move or link a single file, preserving its modification time
+fn transfer_file(source: &Path, dest: &Path, operation: FileOperation) -> Result<()> {
+    match operation {
         FileOperation::Copy => {
             copy_file(source, dest)?;
         }
@@ -1118,6 +1533,7 @@ fn copy_file(source: &Path, dest: &Path) -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::hash::compute_file_hash;
 
     #[test]
     fn test_processing_stats() {
@@ -1206,4 +1622,214 @@ mod tests {
             "IMG_20251006_180527.jpg"
         );
     }
+
+    #[test]
+    fn test_invalid_filename_pattern_rejected() {
+        use crate::config::FilenamePattern;
+
+        let config = Config {
+            filename_patterns: vec![FilenamePattern::with_regex("broken", r"(?P<year>\d{4}")],
+            ..Config::default()
+        };
+        assert!(matches!(Processor::new(config), Err(Error::Config(_))));
+    }
+
+    #[test]
+    fn test_sidecars_follow_primary() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("in/DSC_0001.ARW");
+        fs::create_dir_all(source.parent().unwrap()).unwrap();
+        fs::write(&source, b"raw").unwrap();
+        fs::write(dir.path().join("in/DSC_0001.ARW.xmp"), b"full").unwrap();
+        fs::write(dir.path().join("in/DSC_0001.xmp"), b"stem").unwrap();
+
+        let config = Config {
+            operation: FileOperation::Move,
+            ..Config::default()
+        };
+        let dest = dir.path().join("out/2011/DSC_0001_1.ARW");
+        // A sidecar left at the destination is replaced along with the file
+        let out = dir.path().join("out/2011");
+        fs::create_dir_all(&out).unwrap();
+        fs::write(out.join("DSC_0001_1.xmp"), b"old").unwrap();
+        perform_file_operation(&source, &dest, &xmp::find_sidecars(&source), &config).unwrap();
+
+        assert_eq!(fs::read(&dest).unwrap(), b"raw");
+        assert_eq!(fs::read(out.join("DSC_0001_1.ARW.xmp")).unwrap(), b"full");
+        assert_eq!(fs::read(out.join("DSC_0001_1.xmp")).unwrap(), b"stem");
+        assert!(xmp::find_sidecars(&source).is_empty());
+    }
+
+    #[test]
+    fn test_shared_stem_sidecar_goes_with_raw() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("in");
+        fs::create_dir_all(&input).unwrap();
+        fs::write(input.join("DSC_0001.JPG"), b"jpeg").unwrap();
+        fs::write(input.join("DSC_0001.ARW"), b"raw").unwrap();
+        fs::write(
+            input.join("DSC_0001.xmp"),
+            r#"<rdf:Description exif:DateTimeOriginal="2011-07-24T10:15:30"/>"#,
+        )
+        .unwrap();
+        fs::write(input.join("DSC_0002.JPG"), b"other").unwrap();
+        fs::write(input.join("DSC_0002.xmp"), b"").unwrap();
+
+        let files = [
+            input.join("DSC_0001.JPG"),
+            input.join("DSC_0001.ARW"),
+            input.join("DSC_0002.JPG"),
+        ];
+        let shared = shared_sidecars(&files.iter().collect::<Vec<_>>(), &Config::default());
+        assert_eq!(shared.len(), 1);
+        assert_eq!(
+            shared[&input.join("DSC_0001.xmp")],
+            [input.join("DSC_0001.ARW"), input.join("DSC_0001.JPG")]
+        );
+
+        // Both files of the pair get the sidecar time, the RAW file takes it along
+        let config = Config {
+            input_dirs: vec![input],
+            output_dir: dir.path().join("out"),
+            processing_mode: ProcessingMode::Full,
+            classification: ClassificationRule::Year,
+            operation: FileOperation::Move,
+            ..Config::default()
+        };
+        let mut processor = Processor::new(config).unwrap();
+        let results = processor.run().unwrap();
+        assert!(
+            results
+                .iter()
+                .all(|r| r.status == ProcessingStatus::Success)
+        );
+        let out = dir.path().join("out/2011");
+        assert!(out.join("DSC_0001.ARW").is_file());
+        assert!(out.join("DSC_0001.JPG").is_file());
+        assert!(out.join("DSC_0001.xmp").is_file());
+    }
+
+    #[test]
+    fn test_duplicates_grouped_by_size_then_hash() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("in");
+        fs::create_dir_all(&input).unwrap();
+        fs::write(input.join("IMG_20190501_100000.jpg"), b"same").unwrap();
+        fs::write(input.join("IMG_20190501_100000 (1).jpg"), b"same").unwrap();
+        fs::write(input.join("IMG_20190502_100000.jpg"), b"diff").unwrap();
+        fs::write(input.join("IMG_20190503_100000.jpg"), b"unique size").unwrap();
+
+        let groups = group_by_size(&[
+            input.join("IMG_20190501_100000.jpg"),
+            input.join("IMG_20190503_100000.jpg"),
+            input.join("missing.jpg"),
+        ]);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[&4].len(), 1);
+
+        let config = Config {
+            input_dirs: vec![input],
+            output_dir: dir.path().join("out"),
+            processing_mode: ProcessingMode::Full,
+            dry_run: true,
+            ..Config::default()
+        };
+        let mut processor = Processor::new(config).unwrap();
+        let results = processor.run().unwrap();
+
+        let duplicates: Vec<_> = results
+            .iter()
+            .filter(|r| r.status == ProcessingStatus::Duplicate)
+            .collect();
+        assert_eq!(duplicates.len(), 1);
+        assert!(
+            duplicates[0]
+                .source
+                .ends_with("IMG_20190501_100000 (1).jpg")
+        );
+        assert_eq!(processor.stats().processed.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn test_sampled_hash_match_is_confirmed() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("in");
+        fs::create_dir_all(&input).unwrap();
+
+        // Same head, middle and tail samples, different bytes in between
+        let original = vec![0u8; 4 * 1024 * 1024];
+        let mut edited = original.clone();
+        edited[1024 * 1024 + 1234] = 1;
+        let (a, b) = (
+            input.join("VID_20190501_100000.mp4"),
+            input.join("VID_20190501_100000 (1).mp4"),
+        );
+        fs::write(&a, &original).unwrap();
+        fs::write(&b, &edited).unwrap();
+
+        let config = Config {
+            input_dirs: vec![input],
+            output_dir: dir.path().join("out"),
+            processing_mode: ProcessingMode::Full,
+            large_file_threshold: 1024 * 1024,
+            dry_run: true,
+            ..Config::default()
+        };
+        assert_eq!(
+            compute_file_hash(&a, config.large_file_threshold).unwrap(),
+            compute_file_hash(&b, config.large_file_threshold).unwrap()
+        );
+
+        let mut processor = Processor::new(config).unwrap();
+        let results = processor.run().unwrap();
+        assert!(results.iter().all(|r| r.status == ProcessingStatus::DryRun));
+        assert_eq!(processor.stats().duplicates.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn test_near_duplicates_keep_highest_resolution() {
+        use image::{ImageFormat, Rgb, RgbImage};
+
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("in");
+        fs::create_dir_all(&input).unwrap();
+        let gradient = |width: u32, height: u32| {
+            RgbImage::from_fn(width, height, |x, y| {
+                let v = ((x * 255 / width) + (y * 128 / height)) as u8;
+                Rgb([v, v / 2, 255 - v])
+            })
+        };
+        gradient(400, 300)
+            .save_with_format(input.join("IMG_20190501_100000.png"), ImageFormat::Png)
+            .unwrap();
+        gradient(200, 150)
+            .save_with_format(input.join("mmexport1556704800000.jpg"), ImageFormat::Jpeg)
+            .unwrap();
+
+        let config = Config {
+            input_dirs: vec![input],
+            output_dir: dir.path().join("out"),
+            processing_mode: ProcessingMode::Full,
+            dry_run: true,
+            ..Config::default()
+        };
+        let mut processor = Processor::new(config.clone()).unwrap();
+        let results = processor.run().unwrap();
+        assert!(results.iter().all(|r| r.status == ProcessingStatus::DryRun));
+
+        let mut processor = Processor::new(Config {
+            near_duplicates: true,
+            ..config
+        })
+        .unwrap();
+        let results = processor.run().unwrap();
+        let near: Vec<_> = results
+            .iter()
+            .filter(|r| r.status == ProcessingStatus::NearDuplicate)
+            .collect();
+        assert_eq!(near.len(), 1);
+        assert!(near[0].source.ends_with("mmexport1556704800000.jpg"));
+        assert_eq!(processor.stats().near_duplicates.load(Ordering::Relaxed), 1);
+        assert_eq!(processor.stats().processed.load(Ordering::Relaxed), 1);
+    }
 }
@@ -29,7 +29,7 @@ pub struct ProcessedFile {
     pub dest_path: PathBuf,
 
     /// File content hash
-    pub content_hash: u64,
+    pub content_hash: u128,
 
     /// Metadata hash (size + mtime) for quick change detection
     pub metadata_hash: u64,
@@ -48,7 +48,7 @@ pub struct ProcessingState {
     processed_files: HashMap<PathBuf, ProcessedFile>,
 
     /// Map of content hash to destination path (for deduplication)
-    hash_to_dest: HashMap<u64, PathBuf>,
+    hash_to_dest: HashMap<u128, PathBuf>,
 
     /// Last run timestamp
     last_run: Option<chrono::DateTime<chrono::Utc>>,
@@ -61,8 +61,8 @@ impl Default for ProcessingState {
 }
 
 impl ProcessingState {
-    /// Current state file format version
-    const VERSION: u32 = 1;
+    /// Current state file format version (2: 128-bit content hashes)
+    const VERSION: u32 = 2;
 
     /// Create a new empty state
     pub fn new() -> Self {
@@ -149,7 +149,7 @@ impl ProcessingState {
     }
 
     /// Check if a content hash already exists (duplicate detection)
-    pub fn has_content_hash(&self, content_hash: u64) -> Option<&PathBuf> {
+    pub fn has_content_hash(&self, content_hash: u128) -> Option<&PathBuf> {
         self.hash_to_dest.get(&content_hash)
     }
 
@@ -158,7 +158,7 @@ impl ProcessingState {
         &mut self,
         source_path: PathBuf,
         dest_path: PathBuf,
-        content_hash: u64,
+        content_hash: u128,
         metadata_hash: u64,
     ) {
         let record = ProcessedFile {
@@ -214,23 +214,23 @@ impl ProcessingState {
 /// Increment Metadata file name
 const WATERMARK_FILENAME: &str = ".gallery_sorter_increment_metadata.toml";
 
-/// Serde helper for serializing u64 as hex string (TOML doesn't support u64 > i64::MAX)
-mod hex_u64 {
+/// Serde helper for serializing u128 as hex string (TOML doesn't support integers > i64::MAX)
+mod hex_u128 {
     use serde::{self, Deserialize, Deserializer, Serializer};
 
-    pub fn serialize<S>(value: &u64, serializer: S) -> Result<S::Ok, S::Error>
+    pub fn serialize<S>(value: &u128, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        serializer.serialize_str(&format!("{:016x}", value))
+        serializer.serialize_str(&format!("{:032x}", value))
     }
 
-    pub fn deserialize<'de, D>(deserializer: D) -> Result<u64, D::Error>
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<u128, D::Error>
     where
         D: Deserializer<'de>,
     {
         let s = String::deserialize(deserializer)?;
-        u64::from_str_radix(&s, 16).map_err(serde::de::Error::custom)
+        u128::from_str_radix(&s, 16).map_err(serde::de::Error::custom)
     }
 }
 
@@ -250,8 +250,8 @@ pub struct IncrementalWatermark {
     pub newest_timestamp: NaiveDateTime,
 
     /// Content hash of the newest file (for verification, stored as hex string)
-    #[serde(with = "hex_u64")]
-    pub newest_hash: u64,
+    #[serde(with = "hex_u128")]
+    pub newest_hash: u128,
 
     /// Classification rule used when organizing
     pub classification: ClassificationRule,
@@ -274,7 +274,7 @@ impl IncrementalWatermark {
     pub fn new(
         newest_file_path: PathBuf,
         newest_timestamp: NaiveDateTime,
-        newest_hash: u64,
+        newest_hash: u128,
         classification: ClassificationRule,
         month_format: MonthFormat,
     ) -> Self {
@@ -553,7 +553,7 @@ impl IncrementalWatermark {
     }
 
     /// Update watermark with a new file if it's newer than the current one
-    pub fn update_if_newer(&mut self, file_path: PathBuf, timestamp: NaiveDateTime, hash: u64) {
+    pub fn update_if_newer(&mut self, file_path: PathBuf, timestamp: NaiveDateTime, hash: u128) {
         if timestamp > self.newest_timestamp {
             self.newest_file_path = file_path;
             self.newest_timestamp = timestamp;
@@ -587,7 +587,7 @@ mod tests {
 
         let source = PathBuf::from("/source/file.jpg");
         let dest = PathBuf::from("/dest/2024/01/file.jpg");
-        let content_hash = 12345u64;
+        let content_hash = 12345u128;
         let metadata_hash = 67890u64;
 
         assert!(state.needs_processing(&source, metadata_hash));
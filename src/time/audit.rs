@@ -0,0 +1,272 @@
+//! Cross-source timestamp audit
+//!
+//! Runs every time source configured for a file instead of stopping at the
+//! first one that succeeds, flags files whose sources disagree by more than
+//! `conflict_threshold_hours`, and picks the timestamp to use according to
+//! `conflict_resolution`. File system times are listed but never counted as
+//! a conflict or a vote, since copying files routinely resets them.
+
+use super::{ExtractedTime, TimePrecision, TimeSource, time_candidates};
+use crate::config::{Config, ConflictResolution};
+use crate::error::Result;
+use chrono::{Duration, NaiveDateTime};
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// Every timestamp the configured sources produced for one file
+#[derive(Debug, Clone)]
+pub struct TimeAudit {
+    /// Audited file
+    pub path: PathBuf,
+    /// Plausible timestamps in time source order
+    pub candidates: Vec<ExtractedTime>,
+    /// Index of the timestamp chosen for the file
+    pub chosen: Option<usize>,
+    /// Whether the sources disagree beyond the threshold
+    pub conflict: bool,
+}
+
+impl TimeAudit {
+    /// Run all time sources for a file and choose a timestamp
+    pub fn run(path: &Path, config: &Config) -> Self {
+        let candidates: Vec<_> = time_candidates(path, config).collect();
+        let threshold = Duration::hours(config.conflict_threshold_hours as i64);
+        let conflict = has_conflict(&candidates, threshold);
+        let chosen = choose(&candidates, conflict, threshold, config);
+
+        if conflict {
+            warn!(
+                ?path,
+                candidates = ?candidates
+                    .iter()
+                    .map(|c| (c.source, c.timestamp))
+                    .collect::<Vec<_>>(),
+                chosen = ?chosen.map(|i| candidates[i].source),
+                "Time sources disagree"
+            );
+        }
+
+        Self {
+            path: path.to_path_buf(),
+            candidates,
+            chosen,
+            conflict,
+        }
+    }
+
+    /// The chosen timestamp
+    pub fn into_chosen(mut self) -> Option<ExtractedTime> {
+        self.chosen.map(|i| self.candidates.swap_remove(i))
+    }
+}
+
+/// Whether a candidate counts for conflicts and majority votes
+fn is_evidence(candidate: &ExtractedTime) -> bool {
+    !candidate.source.is_file_system()
+}
+
+/// Whether any two candidates are further apart than `threshold`
+fn has_conflict(candidates: &[ExtractedTime], threshold: Duration) -> bool {
+    let mut times = candidates
+        .iter()
+        .filter(|c| is_evidence(c))
+        .map(|c| c.timestamp);
+    let Some(first) = times.next() else {
+        return false;
+    };
+    let (min, max) = times.fold((first, first), |(min, max), t| (min.min(t), max.max(t)));
+    max - min > threshold
+}
+
+/// Pick the candidate to use; without a conflict the first one wins
+fn choose(
+    candidates: &[ExtractedTime],
+    conflict: bool,
+    threshold: Duration,
+    config: &Config,
+) -> Option<usize> {
+    if candidates.is_empty() {
+        return None;
+    }
+    if !conflict {
+        return Some(0);
+    }
+
+    let chosen = match config.conflict_resolution {
+        ConflictResolution::First => None,
+        ConflictResolution::Majority => {
+            let agreeing = |t: NaiveDateTime| {
+                candidates
+                    .iter()
+                    .filter(|c| is_evidence(c) && (c.timestamp - t).abs() <= threshold)
+                    .count()
+            };
+            // Ties go to the earlier source in the chain
+            candidates
+                .iter()
+                .enumerate()
+                .filter(|(_, c)| is_evidence(c))
+                .max_by_key(|(i, c)| (agreeing(c.timestamp), std::cmp::Reverse(*i)))
+                .map(|(i, _)| i)
+        }
+        ConflictResolution::Prefer => config.preferred_source.and_then(|kind| {
+            candidates
+                .iter()
+                .position(|c| c.source.kind() == Some(kind))
+        }),
+    };
+    chosen.or(Some(0))
+}
+
+#[derive(Serialize)]
+struct Report<'a> {
+    conflict_threshold_hours: u64,
+    conflict_resolution: ConflictResolution,
+    conflicts: usize,
+    files: Vec<ReportEntry<'a>>,
+}
+
+#[derive(Serialize)]
+struct ReportEntry<'a> {
+    path: &'a Path,
+    conflict: bool,
+    chosen: Option<TimeSource>,
+    candidates: Vec<ReportCandidate>,
+}
+
+#[derive(Serialize)]
+struct ReportCandidate {
+    source: TimeSource,
+    timestamp: NaiveDateTime,
+    precision: TimePrecision,
+    offset: Option<String>,
+}
+
+/// Write the audit results as JSON, conflicting files first
+pub fn write_report(path: &Path, audits: &[TimeAudit], config: &Config) -> Result<()> {
+    let mut files: Vec<_> = audits
+        .iter()
+        .map(|audit| ReportEntry {
+            path: &audit.path,
+            conflict: audit.conflict,
+            chosen: audit.chosen.map(|i| audit.candidates[i].source),
+            candidates: audit
+                .candidates
+                .iter()
+                .map(|c| ReportCandidate {
+                    source: c.source,
+                    timestamp: c.timestamp,
+                    precision: c.precision,
+                    offset: c.offset.map(|o| o.to_string()),
+                })
+                .collect(),
+        })
+        .collect();
+    files.sort_by(|a, b| b.conflict.cmp(&a.conflict).then(a.path.cmp(b.path)));
+
+    let report = Report {
+        conflict_threshold_hours: config.conflict_threshold_hours,
+        conflict_resolution: config.conflict_resolution,
+        conflicts: audits.iter().filter(|a| a.conflict).count(),
+        files,
+    };
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(writer, &report)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TimeSourceKind;
+
+    fn candidate(source: TimeSource, s: &str) -> ExtractedTime {
+        ExtractedTime {
+            timestamp: NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap(),
+            source,
+            precision: TimePrecision::Second,
+            offset: None,
+            clock_correction: None,
+        }
+    }
+
+    fn chosen_source(candidates: &[ExtractedTime], config: &Config) -> TimeSource {
+        let threshold = Duration::hours(config.conflict_threshold_hours as i64);
+        let conflict = has_conflict(candidates, threshold);
+        candidates[choose(candidates, conflict, threshold, config).unwrap()].source
+    }
+
+    #[test]
+    fn test_conflict_detection() {
+        let threshold = Duration::hours(24);
+        let agreeing = [
+            candidate(TimeSource::Exif, "2019-05-01 10:00:00"),
+            candidate(TimeSource::Filename, "2019-05-01 12:00:00"),
+            candidate(TimeSource::FileSystem, "2023-01-01 00:00:00"),
+        ];
+        assert!(!has_conflict(&agreeing, threshold));
+
+        let conflicting = [
+            candidate(TimeSource::Exif, "2019-05-01 10:00:00"),
+            candidate(TimeSource::Filename, "2021-05-01 10:00:00"),
+        ];
+        assert!(has_conflict(&conflicting, threshold));
+        assert!(!has_conflict(&[], threshold));
+    }
+
+    #[test]
+    fn test_conflict_resolution() {
+        let candidates = [
+            candidate(TimeSource::Exif, "2000-06-01 10:00:00"),
+            candidate(TimeSource::IsoBmff, "2021-05-01 10:00:00"),
+            candidate(TimeSource::Filename, "2021-05-01 11:00:00"),
+            candidate(TimeSource::FileSystem, "2000-06-01 10:00:00"),
+        ];
+        let mut config = Config::default();
+        assert_eq!(chosen_source(&candidates, &config), TimeSource::Exif);
+
+        config.conflict_resolution = ConflictResolution::Majority;
+        assert_eq!(chosen_source(&candidates, &config), TimeSource::IsoBmff);
+
+        config.conflict_resolution = ConflictResolution::Prefer;
+        config.preferred_source = Some(TimeSourceKind::Filename);
+        assert_eq!(chosen_source(&candidates, &config), TimeSource::Filename);
+
+        // Preferred source without a timestamp falls back to the first one
+        config.preferred_source = Some(TimeSourceKind::Ffprobe);
+        assert_eq!(chosen_source(&candidates, &config), TimeSource::Exif);
+    }
+
+    #[test]
+    fn test_audit_runs_every_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("IMG_20190501_100000.jpg");
+        fs::write(&path, b"not a jpeg").unwrap();
+
+        let config = Config {
+            conflict_resolution: ConflictResolution::Prefer,
+            preferred_source: Some(TimeSourceKind::Mtime),
+            ..Config::default()
+        };
+        let audit = TimeAudit::run(&path, &config);
+        let sources: Vec<_> = audit.candidates.iter().map(|c| c.source).collect();
+        assert_eq!(sources, [TimeSource::Filename, TimeSource::FileSystem]);
+        // The file system time alone is never a conflict
+        assert!(!audit.conflict);
+        assert_eq!(audit.into_chosen().unwrap().source, TimeSource::Filename);
+
+        let report = dir.path().join("audit.json");
+        write_report(&report, &[TimeAudit::run(&path, &config)], &config).unwrap();
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&report).unwrap()).unwrap();
+        assert_eq!(json["conflicts"], 0);
+        assert_eq!(json["files"][0]["candidates"][0]["source"], "Filename");
+    }
+}
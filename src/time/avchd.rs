@@ -0,0 +1,247 @@
+//! Native AVCHD (MTS/M2TS) recording time extraction
+//!
+//! Sony and Panasonic camcorders store the recording date in an H.264 SEI
+//! `user_data_unregistered` message tagged "MDPM" (modified digital video
+//! pack metadata). The transport stream packets are demultiplexed per PID
+//! and the first MDPM block found is decoded:
+//! - tag `0x18`: time zone byte, BCD year (2 bytes), BCD month
+//! - tag `0x19`: BCD day, hour, minute, second
+//!
+//! The decoded value is the camcorder's local wall-clock time.
+
+use crate::error::{Error, Result};
+use crate::time::video::ContainerTime;
+use chrono::{NaiveDate, NaiveDateTime};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use tracing::trace;
+
+/// MPEG transport stream sync byte
+const SYNC_BYTE: u8 = 0x47;
+
+/// Plain transport stream packet size (.mts from some tools, .ts)
+const TS_PACKET_SIZE: usize = 188;
+
+/// BDAV packet size with 4-byte timecode prefix (.mts/.m2ts from camcorders)
+const M2TS_PACKET_SIZE: usize = 192;
+
+/// How much of the stream to scan; MDPM is in the first access unit
+const SCAN_LIMIT: u64 = 4 * 1024 * 1024;
+
+/// UUID of the MDPM SEI message followed by the "MDPM" marker
+const MDPM_SIGNATURE: &[u8] = &[
+    0x17, 0xee, 0x8c, 0x60, 0xf8, 0x4d, 0x11, 0xd9, 0x8c, 0xd6, 0x08, 0x00, 0x20, 0x0c, 0x9a, 0x66,
+    b'M', b'D', b'P', b'M',
+];
+
+/// MDPM tag holding time zone, year and month
+const TAG_DATE: u8 = 0x18;
+
+/// MDPM tag holding day, hour, minute and second
+const TAG_TIME: u8 = 0x19;
+
+/// Extract recording time from an AVCHD transport stream
+pub fn extract_avchd_time(path: &Path) -> Result<ContainerTime> {
+    let make_error = |message: &str| Error::VideoMetadata {
+        path: path.to_path_buf(),
+        message: message.to_string(),
+    };
+
+    // Sniff the packet layout before reading the bulk of the stream
+    let mut file = File::open(path)?;
+    let mut data = Vec::new();
+    (&mut file)
+        .take(M2TS_PACKET_SIZE as u64 * 3)
+        .read_to_end(&mut data)?;
+    let (packet_size, prefix) =
+        detect_packet_layout(&data).ok_or_else(|| make_error("Not an MPEG transport stream"))?;
+    file.take(SCAN_LIMIT - data.len() as u64)
+        .read_to_end(&mut data)?;
+
+    for payload in demux_payloads(&data, packet_size, prefix).values() {
+        if let Some(time) = find_mdpm_time(payload) {
+            trace!(?path, "Found MDPM recording time");
+            return Ok(ContainerTime::Local(time));
+        }
+    }
+
+    Err(make_error("No MDPM recording time found"))
+}
+
+/// Detect packet size and timecode prefix length from sync byte positions
+fn detect_packet_layout(data: &[u8]) -> Option<(usize, usize)> {
+    let is_synced = |size: usize, prefix: usize| {
+        (0..3).all(|i| data.get(prefix + i * size) == Some(&SYNC_BYTE))
+    };
+
+    if is_synced(M2TS_PACKET_SIZE, 4) {
+        Some((M2TS_PACKET_SIZE, 4))
+    } else if is_synced(TS_PACKET_SIZE, 0) {
+        Some((TS_PACKET_SIZE, 0))
+    } else {
+        None
+    }
+}
+
+/// Concatenate packet payloads per PID
+fn demux_payloads(data: &[u8], packet_size: usize, prefix: usize) -> HashMap<u16, Vec<u8>> {
+    let mut streams: HashMap<u16, Vec<u8>> = HashMap::new();
+
+    for packet in data.chunks_exact(packet_size) {
+        let packet = &packet[prefix..];
+        if packet[0] != SYNC_BYTE {
+            continue;
+        }
+        let pid = (((packet[1] & 0x1f) as u16) << 8) | packet[2] as u16;
+        let adaptation = (packet[3] >> 4) & 0x03;
+
+        let start = match adaptation {
+            // Payload only
+            0x01 => 4,
+            // Adaptation field followed by payload
+            0x03 => 5 + packet[4] as usize,
+            _ => continue,
+        };
+        if start < packet.len() {
+            streams
+                .entry(pid)
+                .or_default()
+                .extend_from_slice(&packet[start..]);
+        }
+    }
+
+    streams
+}
+
+/// Search a demultiplexed stream for an MDPM block and decode its date
+fn find_mdpm_time(stream: &[u8]) -> Option<NaiveDateTime> {
+    let pos = stream
+        .windows(MDPM_SIGNATURE.len())
+        .position(|w| w == MDPM_SIGNATURE)?;
+    let block = remove_emulation_prevention(stream.get(pos + MDPM_SIGNATURE.len()..)?);
+
+    let count = *block.first()? as usize;
+    let mut date = None;
+    let mut time = None;
+    for entry in block.get(1..)?.chunks_exact(5).take(count) {
+        match entry[0] {
+            TAG_DATE => date = Some([entry[1], entry[2], entry[3], entry[4]]),
+            TAG_TIME => time = Some([entry[1], entry[2], entry[3], entry[4]]),
+            _ => {}
+        }
+    }
+
+    let [_time_zone, year_hi, year_lo, month] = date?;
+    let [day, hour, minute, second] = time?;
+
+    let year = bcd(year_hi)? as i32 * 100 + bcd(year_lo)? as i32;
+    NaiveDate::from_ymd_opt(year, bcd(month)?, bcd(day)?)?.and_hms_opt(
+        bcd(hour)?,
+        bcd(minute)?,
+        bcd(second)?,
+    )
+}
+
+/// Strip H.264 emulation prevention bytes (00 00 03 -> 00 00)
+fn remove_emulation_prevention(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len().min(512));
+    let mut zeros = 0;
+    // The MDPM block is short; don't copy the rest of the stream
+    for &byte in data.iter().take(512) {
+        if zeros >= 2 && byte == 0x03 {
+            zeros = 0;
+            continue;
+        }
+        zeros = if byte == 0 { zeros + 1 } else { 0 };
+        out.push(byte);
+    }
+    out
+}
+
+/// Decode a packed BCD byte
+fn bcd(byte: u8) -> Option<u32> {
+    let (hi, lo) = (byte >> 4, byte & 0x0f);
+    if hi > 9 || lo > 9 {
+        return None;
+    }
+    Some(hi as u32 * 10 + lo as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Datelike, Timelike};
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn mdpm_payload() -> Vec<u8> {
+        let mut payload = vec![0x00, 0x00, 0x01, 0x06, 0x05, 0x40];
+        payload.extend_from_slice(MDPM_SIGNATURE);
+        payload.push(2);
+        payload.extend_from_slice(&[TAG_DATE, 0x12, 0x20, 0x13, 0x08]);
+        payload.extend_from_slice(&[TAG_TIME, 0x21, 0x17, 0x05, 0x09]);
+        payload
+    }
+
+    fn make_stream(payload: &[u8], packet_size: usize) -> Vec<u8> {
+        let prefix = packet_size - TS_PACKET_SIZE;
+        let mut out = Vec::new();
+        for (i, chunk) in payload.chunks(TS_PACKET_SIZE - 4).enumerate() {
+            out.extend(std::iter::repeat_n(0u8, prefix));
+            out.extend_from_slice(&[SYNC_BYTE, 0x10, 0x11, 0x10 | (i as u8 & 0x0f)]);
+            let mut body = chunk.to_vec();
+            body.resize(TS_PACKET_SIZE - 4, 0xff);
+            out.extend(body);
+        }
+        // Padding packets so the sync pattern is detectable
+        for _ in 0..3 {
+            out.extend(std::iter::repeat_n(0u8, prefix));
+            out.extend_from_slice(&[SYNC_BYTE, 0x1f, 0xff, 0x10]);
+            out.extend(std::iter::repeat_n(0xffu8, TS_PACKET_SIZE - 4));
+        }
+        out
+    }
+
+    #[test]
+    fn test_m2ts_mdpm() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(&make_stream(&mdpm_payload(), M2TS_PACKET_SIZE))
+            .unwrap();
+        file.flush().unwrap();
+
+        let ContainerTime::Local(dt) = extract_avchd_time(file.path()).unwrap() else {
+            panic!("expected local time");
+        };
+        assert_eq!(dt.year(), 2013);
+        assert_eq!(dt.month(), 8);
+        assert_eq!(dt.day(), 21);
+        assert_eq!(dt.hour(), 17);
+        assert_eq!(dt.minute(), 5);
+        assert_eq!(dt.second(), 9);
+    }
+
+    #[test]
+    fn test_plain_ts_mdpm() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(&make_stream(&mdpm_payload(), TS_PACKET_SIZE))
+            .unwrap();
+        file.flush().unwrap();
+        assert!(extract_avchd_time(file.path()).is_ok());
+    }
+
+    #[test]
+    fn test_emulation_prevention() {
+        assert_eq!(
+            remove_emulation_prevention(&[0x00, 0x00, 0x03, 0x01, 0x02]),
+            vec![0x00, 0x00, 0x01, 0x02]
+        );
+    }
+
+    #[test]
+    fn test_bcd() {
+        assert_eq!(bcd(0x59), Some(59));
+        assert_eq!(bcd(0x5a), None);
+    }
+}
@@ -0,0 +1,194 @@
+//! Dates from the names of enclosing directories
+//!
+//! Legacy archives are often sorted by hand into folders such as
+//! `2009-07 Lake Trip/` or `2011/Christmas/`. Walking up from the file to the
+//! input root, the nearest folder whose name starts with a full date, a year
+//! and month, or a year is used. A year or month folder also takes month and
+//! day from numbered subfolders, as in `2011/07/24/`. Missing parts default
+//! to the first day of the period, at midnight; such dates count as guessed.
+
+use crate::time::TimePrecision;
+use crate::time::datetime::parse_date_prefix;
+use chrono::{Datelike, NaiveDate, NaiveDateTime};
+use std::path::Path;
+use tracing::trace;
+
+/// Folder name formats with a full date
+const DAY_FORMATS: &[&str] = &["%Y-%m-%d", "%Y_%m_%d", "%Y.%m.%d", "%Y%m%d"];
+
+/// Folder name formats with year and month
+const MONTH_FORMATS: &[&str] = &["%Y-%m", "%Y_%m", "%Y.%m"];
+
+/// Folder name formats with a year only
+const YEAR_FORMATS: &[&str] = &["%Y"];
+
+/// Precision of a folder date
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Precision {
+    Year,
+    Month,
+    Day,
+}
+
+/// Find the date of the nearest dated folder between `path` and `root`
+///
+/// `root` itself is included; without a root, all ancestors are checked.
+pub fn parse_directory_date(
+    path: &Path,
+    root: Option<&Path>,
+) -> Option<(NaiveDateTime, TimePrecision)> {
+    // Folder names below the current one, nearest to the file first
+    let mut below = Vec::new();
+
+    for dir in path.ancestors().skip(1) {
+        let Some(name) = dir.file_name().and_then(|n| n.to_str()) else {
+            break;
+        };
+        if let Some((date, precision)) = parse_folder_name(name) {
+            let (date, precision) = refine(date, precision, below.iter().rev().copied());
+            trace!(?path, folder = name, %date, ?precision, "Matched directory date");
+            let precision = match precision {
+                Precision::Day => TimePrecision::Day,
+                Precision::Year | Precision::Month => TimePrecision::Guessed,
+            };
+            return Some((date.and_hms_opt(0, 0, 0)?, precision));
+        }
+        if root.is_some_and(|root| dir == root) {
+            break;
+        }
+        below.push(name);
+    }
+
+    None
+}
+
+/// Parse a date at the start of a folder name
+fn parse_folder_name(name: &str) -> Option<(NaiveDate, Precision)> {
+    [
+        (DAY_FORMATS, Precision::Day),
+        (MONTH_FORMATS, Precision::Month),
+        (YEAR_FORMATS, Precision::Year),
+    ]
+    .into_iter()
+    .find_map(|(formats, precision)| {
+        let (date, rest) = parse_date_prefix(name, formats)?;
+        // Reject longer numbers such as "200907" or "12345 Main St"
+        let valid =
+            (1000..=9999).contains(&date.year()) && !rest.starts_with(|c: char| c.is_ascii_digit());
+        valid.then_some((date, precision))
+    })
+}
+
+/// Fill in month and day from numbered subfolders, outermost first
+fn refine<'a>(
+    mut date: NaiveDate,
+    mut precision: Precision,
+    mut children: impl Iterator<Item = &'a str>,
+) -> (NaiveDate, Precision) {
+    if precision == Precision::Year
+        && let Some(month) = children.next().and_then(leading_number)
+        && let Some(refined) = date.with_month(month)
+    {
+        date = refined;
+        precision = Precision::Month;
+    }
+    if precision == Precision::Month
+        && let Some(day) = children.next().and_then(leading_number)
+        && let Some(refined) = date.with_day(day)
+    {
+        date = refined;
+        precision = Precision::Day;
+    }
+    (date, precision)
+}
+
+/// Parse a one or two digit number at the start of a folder name, e.g. "07 July"
+fn leading_number(name: &str) -> Option<u32> {
+    let digits = name.chars().take_while(char::is_ascii_digit).count();
+    if !(1..=2).contains(&digits) {
+        return None;
+    }
+    name[..digits].parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(path: &str, root: Option<&str>) -> Option<String> {
+        parse_directory_date(Path::new(path), root.map(Path::new))
+            .map(|(dt, _)| dt.format("%Y-%m-%d").to_string())
+    }
+
+    #[test]
+    fn test_folder_formats() {
+        assert_eq!(
+            date("archive/2009-07 Lake Trip/DSC_0042.JPG", None).as_deref(),
+            Some("2009-07-01")
+        );
+        assert_eq!(
+            date("archive/2011/Christmas/IMG_1.jpg", None).as_deref(),
+            Some("2011-01-01")
+        );
+        assert_eq!(
+            date("archive/2011-12-24 Christmas Eve/a.jpg", None).as_deref(),
+            Some("2011-12-24")
+        );
+        assert_eq!(
+            date("archive/20111224/a.jpg", None).as_deref(),
+            Some("2011-12-24")
+        );
+        assert_eq!(
+            date("archive/2011.12 Winter/a.jpg", None).as_deref(),
+            Some("2011-12-01")
+        );
+    }
+
+    #[test]
+    fn test_nested_numbered_folders() {
+        assert_eq!(
+            date("archive/2011/07 July/24/a.jpg", None).as_deref(),
+            Some("2011-07-24")
+        );
+        assert_eq!(
+            date("archive/2011-07/24 Beach/a.jpg", None).as_deref(),
+            Some("2011-07-24")
+        );
+        // Not a valid month, keep the year
+        assert_eq!(
+            date("archive/2011/13/a.jpg", None).as_deref(),
+            Some("2011-01-01")
+        );
+    }
+
+    #[test]
+    fn test_precision() {
+        let precision = |path| parse_directory_date(Path::new(path), None).map(|(_, p)| p);
+        assert_eq!(
+            precision("archive/2011-12-24 Christmas Eve/a.jpg"),
+            Some(TimePrecision::Day)
+        );
+        assert_eq!(
+            precision("archive/2011/07/24/a.jpg"),
+            Some(TimePrecision::Day)
+        );
+        assert_eq!(
+            precision("archive/2009-07 Lake Trip/a.jpg"),
+            Some(TimePrecision::Guessed)
+        );
+    }
+
+    #[test]
+    fn test_stops_at_root() {
+        assert_eq!(
+            date("/photos/2011/import/a.jpg", Some("/photos/2011")).as_deref(),
+            Some("2011-01-01")
+        );
+        assert_eq!(
+            date("/2011/photos/import/a.jpg", Some("/2011/photos")),
+            None
+        );
+        assert_eq!(date("archive/DCIM/100CANON/a.jpg", None), None);
+        assert_eq!(date("archive/12345 Main St/a.jpg", None), None);
+    }
+}
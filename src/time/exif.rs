@@ -2,39 +2,108 @@
 
 use crate::error::{Error, Result};
 use crate::time::datetime::parse_exif;
-use chrono::NaiveDateTime;
-use exif::{In, Reader, Tag};
+use crate::time::timezone::GeoLocation;
+use chrono::{FixedOffset, NaiveDate, NaiveDateTime, Timelike};
+use exif::{Exif, In, Reader, Tag, Value};
 use std::fs::File;
 use std::io::BufReader;
 use std::path::Path;
 use tracing::trace;
 
-/// EXIF tags to try for date extraction, in priority order
-const DATE_TAGS: &[Tag] = &[
-    Tag::DateTimeOriginal,  // When the original image was taken
-    Tag::DateTimeDigitized, // When the image was digitized
-    Tag::DateTime,          // File modification date/time
+/// EXIF tags to try for date extraction, in priority order, together with
+/// the offset and sub-second tags that belong to each of them
+const DATE_TAGS: &[(Tag, Tag, Tag)] = &[
+    // When the original image was taken
+    (
+        Tag::DateTimeOriginal,
+        Tag::OffsetTimeOriginal,
+        Tag::SubSecTimeOriginal,
+    ),
+    // When the image was digitized
+    (
+        Tag::DateTimeDigitized,
+        Tag::OffsetTimeDigitized,
+        Tag::SubSecTimeDigitized,
+    ),
+    // File modification date/time
+    (Tag::DateTime, Tag::OffsetTime, Tag::SubSecTime),
 ];
 
-/// Extract creation time from EXIF metadata
-pub fn extract_exif_time(path: &Path) -> Result<NaiveDateTime> {
+/// Date read from EXIF metadata
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExifTime {
+    /// Local date and time, including sub-seconds when recorded
+    pub datetime: NaiveDateTime,
+    /// UTC offset of `datetime`, when recorded
+    pub offset: Option<FixedOffset>,
+    /// UTC time from `GPSDateStamp`/`GPSTimeStamp`, when recorded
+    pub gps_time: Option<NaiveDateTime>,
+    /// GPS coordinates of the shot, when recorded
+    pub location: Option<GeoLocation>,
+}
+
+/// Camera body recorded in EXIF metadata
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CameraInfo {
+    /// `Make` tag
+    pub make: Option<String>,
+    /// `Model` tag
+    pub model: Option<String>,
+    /// `BodySerialNumber` tag
+    pub serial: Option<String>,
+}
+
+/// Read the EXIF block of a file
+fn read_exif(path: &Path) -> Result<Exif> {
     let file = File::open(path)?;
     let mut reader = BufReader::new(file);
 
-    let exif = Reader::new()
+    Reader::new()
         .read_from_container(&mut reader)
         .map_err(|e| Error::ExifRead {
             path: path.to_path_buf(),
             message: e.to_string(),
-        })?;
+        })
+}
+
+/// Extract the camera make, model and serial number from EXIF metadata
+pub fn extract_camera_info(path: &Path) -> Result<CameraInfo> {
+    let exif = read_exif(path)?;
+    Ok(CameraInfo {
+        make: read_ascii(&exif, Tag::Make),
+        model: read_ascii(&exif, Tag::Model),
+        serial: read_ascii(&exif, Tag::BodySerialNumber),
+    })
+}
+
+/// Extract creation time from EXIF metadata
+pub fn extract_exif_time(path: &Path) -> Result<ExifTime> {
+    let exif = read_exif(path)?;
 
     // Try each date tag in priority order
-    for tag in DATE_TAGS {
-        if let Some(field) = exif.get_field(*tag, In::PRIMARY)
-            && let Some(datetime) = parse_exif(&field.display_value().to_string())
+    for (date_tag, offset_tag, subsec_tag) in DATE_TAGS {
+        if let Some(field) = exif.get_field(*date_tag, In::PRIMARY)
+            && let Some(mut datetime) = parse_exif(&field.display_value().to_string())
         {
-            trace!(?path, ?tag, "Found EXIF date");
-            return Ok(datetime);
+            if let Some(nanos) = read_ascii(&exif, *subsec_tag).and_then(|s| parse_subsec(&s)) {
+                datetime = datetime.with_nanosecond(nanos).unwrap_or(datetime);
+            }
+
+            // OffsetTime applies to DateTime and is the common fallback
+            let offset = read_ascii(&exif, *offset_tag)
+                .or_else(|| read_ascii(&exif, Tag::OffsetTime))
+                .and_then(|s| parse_offset(&s));
+
+            let gps_time = read_gps_time(&exif);
+            let location = read_gps_location(&exif);
+
+            trace!(?path, tag = ?date_tag, ?offset, ?gps_time, ?location, "Found EXIF date");
+            return Ok(ExifTime {
+                datetime,
+                offset,
+                gps_time,
+                location,
+            });
         }
     }
 
@@ -44,6 +113,96 @@ pub fn extract_exif_time(path: &Path) -> Result<NaiveDateTime> {
     })
 }
 
+/// Read the first string of an ASCII field
+fn read_ascii(exif: &Exif, tag: Tag) -> Option<String> {
+    let field = exif.get_field(tag, In::PRIMARY)?;
+    match field.value {
+        Value::Ascii(ref values) => values
+            .first()
+            .map(|v| String::from_utf8_lossy(v).trim().to_string())
+            .filter(|v| !v.is_empty()),
+        _ => None,
+    }
+}
+
+/// Read a rational triple such as degrees/minutes/seconds or h/m/s
+fn read_triple(exif: &Exif, tag: Tag) -> Option<[f64; 3]> {
+    let field = exif.get_field(tag, In::PRIMARY)?;
+    match field.value {
+        Value::Rational(ref values) if values.len() >= 3 => {
+            let mut triple = [0.0; 3];
+            for (out, value) in triple.iter_mut().zip(values) {
+                if value.denom == 0 {
+                    return None;
+                }
+                *out = value.to_f64();
+            }
+            Some(triple)
+        }
+        _ => None,
+    }
+}
+
+/// Read the UTC time recorded by the GPS receiver
+fn read_gps_time(exif: &Exif) -> Option<NaiveDateTime> {
+    let date = read_ascii(exif, Tag::GPSDateStamp)?;
+    let date = NaiveDate::parse_from_str(&date, "%Y:%m:%d").ok()?;
+    let [hours, minutes, seconds] = read_triple(exif, Tag::GPSTimeStamp)?;
+    date.and_hms_opt(hours as u32, minutes as u32, seconds as u32)
+}
+
+/// Read the GPS coordinates of the shot
+fn read_gps_location(exif: &Exif) -> Option<GeoLocation> {
+    let latitude = to_degrees(read_triple(exif, Tag::GPSLatitude)?);
+    let longitude = to_degrees(read_triple(exif, Tag::GPSLongitude)?);
+    let latitude = match read_ascii(exif, Tag::GPSLatitudeRef)?.as_str() {
+        "S" => -latitude,
+        _ => latitude,
+    };
+    let longitude = match read_ascii(exif, Tag::GPSLongitudeRef)?.as_str() {
+        "W" => -longitude,
+        _ => longitude,
+    };
+    GeoLocation::new(latitude, longitude)
+}
+
+/// Convert degrees/minutes/seconds to decimal degrees
+fn to_degrees([degrees, minutes, seconds]: [f64; 3]) -> f64 {
+    degrees + minutes / 60.0 + seconds / 3600.0
+}
+
+/// Parse an EXIF offset string such as "+08:00" or "-05:30"
+fn parse_offset(s: &str) -> Option<FixedOffset> {
+    let s = s.trim().trim_matches('"');
+    let sign = match s.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    let (hours, minutes) = s[1..].split_once(':')?;
+    let hours: i32 = hours.parse().ok()?;
+    let minutes: i32 = minutes.parse().ok()?;
+    if hours > 14 || minutes > 59 {
+        return None;
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+/// Parse an EXIF sub-second string (decimal fraction digits) into nanoseconds
+fn parse_subsec(s: &str) -> Option<u32> {
+    let digits: String = s
+        .trim()
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .take(9)
+        .collect();
+    if digits.is_empty() {
+        return None;
+    }
+    let value: u32 = digits.parse().ok()?;
+    Some(value * 10u32.pow(9 - digits.len() as u32))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -71,4 +230,29 @@ mod tests {
         // Invalid format
         assert!(parse_exif("invalid").is_none());
     }
+
+    #[test]
+    fn test_parse_offset() {
+        assert_eq!(parse_offset("+08:00").unwrap().local_minus_utc(), 8 * 3600);
+        assert_eq!(
+            parse_offset("-05:30").unwrap().local_minus_utc(),
+            -(5 * 3600 + 30 * 60)
+        );
+        assert!(parse_offset("   :  ").is_none());
+        assert!(parse_offset("08:00").is_none());
+    }
+
+    #[test]
+    fn test_to_degrees() {
+        assert!((to_degrees([48.0, 51.0, 30.24]) - 48.8584).abs() < 1e-9);
+        assert_eq!(to_degrees([13.0, 0.0, 0.0]), 13.0);
+    }
+
+    #[test]
+    fn test_parse_subsec() {
+        assert_eq!(parse_subsec("5"), Some(500_000_000));
+        assert_eq!(parse_subsec("123"), Some(123_000_000));
+        assert_eq!(parse_subsec("045 "), Some(45_000_000));
+        assert!(parse_subsec("").is_none());
+    }
 }
@@ -0,0 +1,230 @@
+//! Google Takeout JSON metadata
+//!
+//! Takeout strips EXIF from many items and ships the capture time and
+//! location in a JSON file next to each one instead. Its naming has a few
+//! quirks, all of which are tried:
+//!
+//! - `IMG_1234.jpg.json`, or `IMG_1234.jpg.supplemental-metadata.json` in
+//!   newer exports
+//! - Names longer than 46 characters are cut before `.json`, as in
+//!   `PXL_20230612_183012345.NIGHT.jpg.supplemental-.json`
+//! - The `(1)` of renamed duplicates moves behind the extension:
+//!   `IMG_1234(1).jpg` belongs to `IMG_1234.jpg(1).json`
+//! - Edited copies (`IMG_1234-edited.jpg`) share the original's JSON
+//! - Some items use the name without extension, `IMG_1234.json`
+//!
+//! `photoTakenTime` is a UTC epoch; `creationTime` is the upload time and is
+//! ignored.
+
+use crate::error::{Error, Result};
+use crate::time::timezone::GeoLocation;
+use crate::time::video::ContainerTime;
+use chrono::DateTime;
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::trace;
+
+/// Longest JSON filename Takeout writes, without `.json`
+const MAX_JSON_STEM_CHARS: usize = 46;
+
+/// Suffix of newer Takeout metadata files
+const SUPPLEMENTAL_SUFFIX: &str = ".supplemental-metadata";
+
+/// Suffix Google Photos adds to edited copies
+const EDITED_SUFFIX: &str = "-edited";
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TakeoutMetadata {
+    photo_taken_time: Option<TakeoutTime>,
+    geo_data: Option<TakeoutGeo>,
+    geo_data_exif: Option<TakeoutGeo>,
+}
+
+#[derive(Deserialize)]
+struct TakeoutTime {
+    /// Seconds since the epoch, as a string
+    timestamp: String,
+}
+
+#[derive(Deserialize)]
+struct TakeoutGeo {
+    latitude: f64,
+    longitude: f64,
+}
+
+impl TakeoutGeo {
+    /// Takeout writes 0/0 when the location is unknown
+    fn location(&self) -> Option<GeoLocation> {
+        if self.latitude == 0.0 && self.longitude == 0.0 {
+            return None;
+        }
+        GeoLocation::new(self.latitude, self.longitude)
+    }
+}
+
+/// Split a trailing `(n)` off a file stem
+fn split_counter(stem: &str) -> (&str, &str) {
+    if let Some(open) = stem.rfind('(')
+        && stem.ends_with(')')
+        && stem[open + 1..stem.len() - 1]
+            .bytes()
+            .all(|b| b.is_ascii_digit())
+        && open + 2 < stem.len()
+    {
+        return (&stem[..open], &stem[open..]);
+    }
+    (stem, "")
+}
+
+/// Takeout's name for the JSON of `base`, cut to the maximum length
+fn json_name(base: &str, counter: &str) -> String {
+    let cut: String = base.chars().take(MAX_JSON_STEM_CHARS).collect();
+    format!("{cut}{counter}.json")
+}
+
+/// Candidate JSON files for a media file, most specific first
+pub fn metadata_candidates(path: &Path) -> Vec<PathBuf> {
+    let (Some(name), Some(stem)) = (
+        path.file_name().and_then(|n| n.to_str()),
+        path.file_stem().and_then(|s| s.to_str()),
+    ) else {
+        return Vec::new();
+    };
+    let extension = &name[stem.len()..];
+    let (stem, counter) = split_counter(stem);
+    let original = stem.strip_suffix(EDITED_SUFFIX).unwrap_or(stem);
+
+    let mut names = Vec::new();
+    for stem in [stem, original] {
+        let name = format!("{stem}{extension}");
+        names.push(json_name(&name, counter));
+        names.push(json_name(&format!("{name}{SUPPLEMENTAL_SUFFIX}"), counter));
+        names.push(json_name(stem, counter));
+    }
+    if !counter.is_empty() {
+        // Some exports keep the counter on the media name instead
+        names.push(json_name(name, ""));
+    }
+
+    let mut candidates: Vec<PathBuf> = Vec::new();
+    for name in names {
+        let candidate = path.with_file_name(name);
+        if !candidates.contains(&candidate) {
+            candidates.push(candidate);
+        }
+    }
+    candidates
+}
+
+/// Read the capture time from the Takeout JSON of a media file
+pub fn extract_export_time(path: &Path) -> Result<ContainerTime> {
+    let json = metadata_candidates(path)
+        .into_iter()
+        .find(|candidate| candidate.is_file())
+        .ok_or_else(|| Error::TimestampParse {
+            source_info: path.display().to_string(),
+            message: "No export metadata found".to_string(),
+        })?;
+    trace!(?path, ?json, "Found export metadata");
+    parse_export_metadata(&fs::read_to_string(&json)?).ok_or_else(|| Error::TimestampParse {
+        source_info: json.display().to_string(),
+        message: "No photoTakenTime in export metadata".to_string(),
+    })
+}
+
+/// Parse the capture time and location from Takeout JSON
+fn parse_export_metadata(json: &str) -> Option<ContainerTime> {
+    let metadata: TakeoutMetadata = serde_json::from_str(json).ok()?;
+    let seconds: i64 = metadata.photo_taken_time?.timestamp.trim().parse().ok()?;
+    let utc = DateTime::from_timestamp(seconds, 0)?.naive_utc();
+
+    let location = [metadata.geo_data, metadata.geo_data_exif]
+        .iter()
+        .flatten()
+        .find_map(TakeoutGeo::location);
+    Some(match location {
+        Some(location) => ContainerTime::Located(utc, location),
+        None => ContainerTime::Utc(utc),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate_names(path: &str) -> Vec<String> {
+        metadata_candidates(Path::new(path))
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn test_metadata_candidates() {
+        let names = candidate_names("Takeout/IMG_1234.jpg");
+        assert_eq!(names[0], "IMG_1234.jpg.json");
+        assert_eq!(names[1], "IMG_1234.jpg.supplemental-metadata.json");
+        assert!(names.contains(&"IMG_1234.json".to_string()));
+
+        let names = candidate_names("Takeout/IMG_1234(1).jpg");
+        assert_eq!(names[0], "IMG_1234.jpg(1).json");
+        assert!(names.contains(&"IMG_1234.jpg.supplemental-metadata(1).json".to_string()));
+        assert!(names.contains(&"IMG_1234(1).jpg.json".to_string()));
+
+        let names = candidate_names("Takeout/IMG_1234-edited.jpg");
+        assert!(names.contains(&"IMG_1234.jpg.json".to_string()));
+
+        let names = candidate_names("Takeout/PXL_20230612_183012345.NIGHT.jpg");
+        assert!(names.contains(&"PXL_20230612_183012345.NIGHT.jpg.supplemental-.json".to_string()));
+
+        let long = "Screenshot_2019-05-01-10-00-00-123_com.example.app.png";
+        let names = candidate_names(long);
+        assert_eq!(
+            names[0],
+            "Screenshot_2019-05-01-10-00-00-123_com.example.json"
+        );
+    }
+
+    #[test]
+    fn test_parse_export_metadata() {
+        let json = r#"{
+            "title": "IMG_1234.jpg",
+            "creationTime": { "timestamp": "1600000000", "formatted": "..." },
+            "photoTakenTime": { "timestamp": "1311501600", "formatted": "..." },
+            "geoData": { "latitude": 0.0, "longitude": 0.0, "altitude": 0.0 },
+            "geoDataExif": { "latitude": 48.8584, "longitude": 2.2945, "altitude": 35.0 }
+        }"#;
+        let Some(ContainerTime::Located(utc, location)) = parse_export_metadata(json) else {
+            panic!("expected a located time");
+        };
+        assert_eq!(utc.to_string(), "2011-07-24 10:00:00");
+        assert!((location.latitude - 48.8584).abs() < 1e-9);
+
+        let json = r#"{ "photoTakenTime": { "timestamp": "1311501600" } }"#;
+        assert!(matches!(
+            parse_export_metadata(json),
+            Some(ContainerTime::Utc(_))
+        ));
+        assert!(parse_export_metadata(r#"{ "title": "a.jpg" }"#).is_none());
+    }
+
+    #[test]
+    fn test_extract_export_time() {
+        let dir = tempfile::tempdir().unwrap();
+        let photo = dir.path().join("IMG_1234(1).jpg");
+        fs::write(&photo, b"jpeg").unwrap();
+        assert!(extract_export_time(&photo).is_err());
+
+        fs::write(
+            dir.path().join("IMG_1234.jpg(1).json"),
+            r#"{ "photoTakenTime": { "timestamp": "1311501600" } }"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            extract_export_time(&photo).unwrap(),
+            ContainerTime::Utc(_)
+        ));
+    }
+}
@@ -1,6 +1,9 @@
 //! Filename timestamp parsing
 
-use chrono::NaiveDateTime;
+use crate::config::{FilenamePattern, PatternPrecedence};
+use crate::time::TimePrecision;
+use crate::time::video::ContainerTime;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
 use regex::Regex;
 use std::sync::OnceLock;
 use tracing::trace;
@@ -26,6 +29,70 @@ static PATTERN_SCREENSHOT: OnceLock<Regex> = OnceLock::new();
 /// Pattern: WhatsApp format (IMG-YYYYMMDD-WAxxxx)
 static PATTERN_WHATSAPP: OnceLock<Regex> = OnceLock::new();
 
+/// Pattern: WeChat export (mmexport + epoch milliseconds)
+static PATTERN_WECHAT: OnceLock<Regex> = OnceLock::new();
+
+/// Pattern: Telegram Desktop export (photo_YYYY-MM-DD_HH-mm-ss)
+static PATTERN_TELEGRAM: OnceLock<Regex> = OnceLock::new();
+
+/// Pattern: Signal export (signal-YYYY-MM-DD-HHmmss)
+static PATTERN_SIGNAL: OnceLock<Regex> = OnceLock::new();
+
+/// Pattern: Google Pixel camera (PXL_YYYYMMDD_HHmmssSSS, UTC)
+static PATTERN_PIXEL: OnceLock<Regex> = OnceLock::new();
+
+/// Pattern: Samsung camera burst shot (YYYYMMDD_HHmmss(n))
+///
+/// The bare YYYYMMDD_HHmmss form is used by many cameras and tools, so only
+/// the burst counter identifies Samsung.
+static PATTERN_SAMSUNG: OnceLock<Regex> = OnceLock::new();
+
+/// App recognizers, tried in order before the generic patterns
+const APP_RECOGNIZERS: &[fn(&str) -> Option<AppMatch>] = &[
+    recognize_whatsapp,
+    recognize_wechat,
+    recognize_telegram,
+    recognize_signal,
+    recognize_pixel,
+    recognize_samsung,
+];
+
+/// App or device whose naming scheme a filename follows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceApp {
+    WhatsApp,
+    WeChat,
+    Telegram,
+    Signal,
+    Pixel,
+    Samsung,
+}
+
+impl SourceApp {
+    /// Folder name used when routing files by source app
+    pub fn folder_name(&self) -> &'static str {
+        match self {
+            SourceApp::WhatsApp => "WhatsApp",
+            SourceApp::WeChat => "WeChat",
+            SourceApp::Telegram => "Telegram",
+            SourceApp::Signal => "Signal",
+            SourceApp::Pixel => "Pixel",
+            SourceApp::Samsung => "Samsung",
+        }
+    }
+}
+
+/// Timestamp found by an app recognizer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AppMatch {
+    /// App the filename was identified as
+    pub app: SourceApp,
+    /// Timestamp encoded in the filename
+    pub time: ContainerTime,
+    /// How much of the time the filename encodes
+    pub precision: TimePrecision,
+}
+
 /// Get the compact pattern
 fn get_pattern_compact() -> &'static Regex {
     PATTERN_COMPACT
@@ -65,59 +132,223 @@ fn get_pattern_whatsapp() -> &'static Regex {
         .get_or_init(|| Regex::new(r"(?:IMG|VID)[-_](\d{4})(\d{2})(\d{2})[-_]WA").unwrap())
 }
 
-/// Parse timestamp from filename using various patterns
+/// Get the WeChat pattern
+fn get_pattern_wechat() -> &'static Regex {
+    PATTERN_WECHAT.get_or_init(|| Regex::new(r"^(?:mmexport|wx_camera_)(\d{13})").unwrap())
+}
+
+/// Get the Telegram pattern
+fn get_pattern_telegram() -> &'static Regex {
+    PATTERN_TELEGRAM.get_or_init(|| {
+        Regex::new(r"^(?:photo|video)_(\d{4})-(\d{2})-(\d{2})_(\d{2})-(\d{2})-(\d{2})").unwrap()
+    })
+}
+
+/// Get the Signal pattern
+fn get_pattern_signal() -> &'static Regex {
+    PATTERN_SIGNAL.get_or_init(|| {
+        Regex::new(r"^signal-(\d{4})-(\d{2})-(\d{2})-(\d{2})-?(\d{2})-?(\d{2})").unwrap()
+    })
+}
+
+/// Get the Pixel pattern
+fn get_pattern_pixel() -> &'static Regex {
+    PATTERN_PIXEL.get_or_init(|| {
+        Regex::new(r"^PXL_(\d{4})(\d{2})(\d{2})_(\d{2})(\d{2})(\d{2})(\d{3})").unwrap()
+    })
+}
+
+/// Get the Samsung pattern
+fn get_pattern_samsung() -> &'static Regex {
+    PATTERN_SAMSUNG.get_or_init(|| {
+        Regex::new(r"^(\d{4})(\d{2})(\d{2})_(\d{2})(\d{2})(\d{2})\(\d+\)$").unwrap()
+    })
+}
+
+/// Remove the extension from a filename, keeping names that are all extension
+fn strip_extension(filename: &str) -> &str {
+    match filename.rsplit_once('.') {
+        Some((name, _)) if !name.is_empty() => name,
+        _ => filename,
+    }
+}
+
+/// Identify the app that named a file and read its timestamp
+pub fn recognize_app(filename: &str) -> Option<AppMatch> {
+    let name = strip_extension(filename);
+    APP_RECOGNIZERS.iter().find_map(|recognize| recognize(name))
+}
+
+/// Parse timestamp from filename using the built-in patterns
 pub fn parse_filename_time(filename: &str) -> Option<NaiveDateTime> {
+    parse_filename_timestamp(filename, &[]).map(|(time, _)| time.naive())
+}
+
+/// Parse timestamp from filename, telling local wall-clock times apart from
+/// UTC Unix timestamps, together with how much of the time the name encodes
+///
+/// Custom patterns are tried before or after the built-in ones according to
+/// their precedence.
+pub fn parse_filename_timestamp(
+    filename: &str,
+    custom: &[FilenamePattern],
+) -> Option<(ContainerTime, TimePrecision)> {
     // Remove extension for cleaner parsing
-    let name = filename
-        .rsplit('.')
-        .skip(1)
-        .collect::<Vec<_>>()
-        .into_iter()
-        .rev()
-        .collect::<Vec<_>>()
-        .join(".");
-    let name = if name.is_empty() { filename } else { &name };
+    let name = strip_extension(filename);
+
+    try_custom_patterns(name, custom, PatternPrecedence::First)
+        .or_else(|| try_builtin_patterns(name))
+        .or_else(|| try_custom_patterns(name, custom, PatternPrecedence::Last))
+}
+
+/// Try the user-defined patterns with the given precedence, in order
+fn try_custom_patterns(
+    name: &str,
+    patterns: &[FilenamePattern],
+    precedence: PatternPrecedence,
+) -> Option<(ContainerTime, TimePrecision)> {
+    patterns
+        .iter()
+        .filter(|pattern| pattern.precedence == precedence)
+        .find_map(|pattern| {
+            let time = match (&pattern.format, pattern.compiled_regex()) {
+                (Some(format), _) => try_custom_format(name, format),
+                (None, Some(regex)) => try_custom_regex(name, regex),
+                _ => None,
+            }?;
+            trace!(name, pattern = pattern.name, "Matched custom pattern");
+            Some(time)
+        })
+}
+
+/// Match a chrono format string starting anywhere in the name
+///
+/// Formats without time fields yield midnight; formats using `%s` are UTC.
+fn try_custom_format(name: &str, format: &str) -> Option<(ContainerTime, TimePrecision)> {
+    let is_utc = format.contains("%s");
+    let precision = if ["%s", "%S", "%T"].iter().any(|spec| format.contains(spec)) {
+        TimePrecision::Second
+    } else if ["%M", "%R"].iter().any(|spec| format.contains(spec)) {
+        TimePrecision::Minute
+    } else {
+        TimePrecision::Day
+    };
+    name.char_indices().find_map(|(start, _)| {
+        let s = &name[start..];
+        let dt = NaiveDateTime::parse_and_remainder(s, format)
+            .map(|(dt, _)| dt)
+            .or_else(|_| {
+                NaiveDate::parse_and_remainder(s, format).map(|(d, _)| d.and_time(NaiveTime::MIN))
+            })
+            .ok()?;
+        let time = if is_utc {
+            ContainerTime::Utc(dt)
+        } else {
+            ContainerTime::Local(dt)
+        };
+        Some((time, precision))
+    })
+}
+
+/// Match a regex with named `year`/`month`/`day`/... or `epoch` groups
+///
+/// Missing time groups default to zero; two-digit years are taken as 20xx.
+fn try_custom_regex(name: &str, regex: &Regex) -> Option<(ContainerTime, TimePrecision)> {
+    let caps = regex.captures(name)?;
+    let group = |group: &str| caps.name(group).map(|m| m.as_str());
+
+    if let Some(epoch) = group("epoch") {
+        let value: i64 = epoch.parse().ok()?;
+        let dt = if epoch.len() >= 13 {
+            chrono::DateTime::from_timestamp_millis(value)
+        } else {
+            chrono::DateTime::from_timestamp(value, 0)
+        }?;
+        return Some((ContainerTime::Utc(dt.naive_utc()), TimePrecision::Second));
+    }
+
+    let number = |name: &str| -> Option<u32> {
+        match group(name) {
+            Some(s) => s.parse().ok(),
+            None => Some(0),
+        }
+    };
+
+    let year_str = group("year")?;
+    let year: i32 = year_str.parse().ok()?;
+    let year = if year_str.len() == 2 {
+        2000 + year
+    } else {
+        year
+    };
+    let date = NaiveDate::from_ymd_opt(
+        year,
+        group("month")?.parse().ok()?,
+        group("day")?.parse().ok()?,
+    )?;
+    let time = NaiveTime::from_hms_milli_opt(
+        number("hour")?,
+        number("minute")?,
+        number("second")?,
+        number("millis")?,
+    )?;
+    let precision = if group("second").is_some() {
+        TimePrecision::Second
+    } else if group("minute").is_some() {
+        TimePrecision::Minute
+    } else {
+        TimePrecision::Day
+    };
+    Some((ContainerTime::Local(date.and_time(time)), precision))
+}
+
+/// Try the built-in patterns, most specific first
+fn try_builtin_patterns(name: &str) -> Option<(ContainerTime, TimePrecision)> {
+    // Try app naming schemes first (most specific)
+    if let Some(AppMatch {
+        app,
+        time,
+        precision,
+    }) = APP_RECOGNIZERS.iter().find_map(|r| r(name))
+    {
+        trace!(name, app = app.folder_name(), "Matched app pattern");
+        return Some((time, precision));
+    }
 
     // Try compact format first (most common)
     if let Some(dt) = try_pattern_compact(name) {
-        trace!(filename, "Matched compact pattern");
-        return Some(dt);
+        trace!(name, "Matched compact pattern");
+        return Some((ContainerTime::Local(dt), TimePrecision::Second));
     }
 
     // Try IMG/VID prefix pattern
     if let Some(dt) = try_pattern_img(name) {
-        trace!(filename, "Matched IMG pattern");
-        return Some(dt);
+        trace!(name, "Matched IMG pattern");
+        return Some((ContainerTime::Local(dt), TimePrecision::Second));
     }
 
     // Try screenshot pattern
     if let Some(dt) = try_pattern_screenshot(name) {
-        trace!(filename, "Matched screenshot pattern");
-        return Some(dt);
+        trace!(name, "Matched screenshot pattern");
+        return Some((ContainerTime::Local(dt), TimePrecision::Second));
     }
 
     // Try separated format
     if let Some(dt) = try_pattern_separated(name) {
-        trace!(filename, "Matched separated pattern");
-        return Some(dt);
-    }
-
-    // Try WhatsApp pattern
-    if let Some(dt) = try_pattern_whatsapp(name) {
-        trace!(filename, "Matched WhatsApp pattern");
-        return Some(dt);
+        trace!(name, "Matched separated pattern");
+        return Some((ContainerTime::Local(dt), TimePrecision::Second));
     }
 
     // Try Unix timestamp
     if let Some(dt) = try_pattern_unix(name) {
-        trace!(filename, "Matched Unix timestamp pattern");
-        return Some(dt);
+        trace!(name, "Matched Unix timestamp pattern");
+        return Some((ContainerTime::Utc(dt), TimePrecision::Second));
     }
 
     // Try date only as last resort
     if let Some(dt) = try_pattern_date_only(name) {
-        trace!(filename, "Matched date-only pattern");
-        return Some(dt);
+        trace!(name, "Matched date-only pattern");
+        return Some((ContainerTime::Local(dt), TimePrecision::Day));
     }
 
     None
@@ -125,50 +356,22 @@ pub fn parse_filename_time(filename: &str) -> Option<NaiveDateTime> {
 
 fn try_pattern_compact(s: &str) -> Option<NaiveDateTime> {
     let caps = get_pattern_compact().captures(s)?;
-    build_datetime(
-        caps.get(1)?.as_str(),
-        caps.get(2)?.as_str(),
-        caps.get(3)?.as_str(),
-        caps.get(4)?.as_str(),
-        caps.get(5)?.as_str(),
-        caps.get(6)?.as_str(),
-    )
+    build_datetime_from_captures(&caps)
 }
 
 fn try_pattern_img(s: &str) -> Option<NaiveDateTime> {
     let caps = get_pattern_img().captures(s)?;
-    build_datetime(
-        caps.get(1)?.as_str(),
-        caps.get(2)?.as_str(),
-        caps.get(3)?.as_str(),
-        caps.get(4)?.as_str(),
-        caps.get(5)?.as_str(),
-        caps.get(6)?.as_str(),
-    )
+    build_datetime_from_captures(&caps)
 }
 
 fn try_pattern_screenshot(s: &str) -> Option<NaiveDateTime> {
     let caps = get_pattern_screenshot().captures(s)?;
-    build_datetime(
-        caps.get(1)?.as_str(),
-        caps.get(2)?.as_str(),
-        caps.get(3)?.as_str(),
-        caps.get(4)?.as_str(),
-        caps.get(5)?.as_str(),
-        caps.get(6)?.as_str(),
-    )
+    build_datetime_from_captures(&caps)
 }
 
 fn try_pattern_separated(s: &str) -> Option<NaiveDateTime> {
     let caps = get_pattern_separated().captures(s)?;
-    build_datetime(
-        caps.get(1)?.as_str(),
-        caps.get(2)?.as_str(),
-        caps.get(3)?.as_str(),
-        caps.get(4)?.as_str(),
-        caps.get(5)?.as_str(),
-        caps.get(6)?.as_str(),
-    )
+    build_datetime_from_captures(&caps)
 }
 
 fn try_pattern_whatsapp(s: &str) -> Option<NaiveDateTime> {
@@ -183,6 +386,72 @@ fn try_pattern_whatsapp(s: &str) -> Option<NaiveDateTime> {
     )
 }
 
+fn recognize_whatsapp(s: &str) -> Option<AppMatch> {
+    Some(AppMatch {
+        app: SourceApp::WhatsApp,
+        time: ContainerTime::Local(try_pattern_whatsapp(s)?),
+        precision: TimePrecision::Day,
+    })
+}
+
+fn recognize_wechat(s: &str) -> Option<AppMatch> {
+    let caps = get_pattern_wechat().captures(s)?;
+    let millis: i64 = caps.get(1)?.as_str().parse().ok()?;
+    if !is_reasonable_epoch(millis / 1000) {
+        return None;
+    }
+    let dt = chrono::DateTime::from_timestamp_millis(millis)?;
+    Some(AppMatch {
+        app: SourceApp::WeChat,
+        time: ContainerTime::Utc(dt.naive_utc()),
+        precision: TimePrecision::Second,
+    })
+}
+
+fn recognize_telegram(s: &str) -> Option<AppMatch> {
+    let caps = get_pattern_telegram().captures(s)?;
+    Some(AppMatch {
+        app: SourceApp::Telegram,
+        time: ContainerTime::Local(build_datetime_from_captures(&caps)?),
+        precision: TimePrecision::Second,
+    })
+}
+
+fn recognize_signal(s: &str) -> Option<AppMatch> {
+    let caps = get_pattern_signal().captures(s)?;
+    Some(AppMatch {
+        app: SourceApp::Signal,
+        time: ContainerTime::Local(build_datetime_from_captures(&caps)?),
+        precision: TimePrecision::Second,
+    })
+}
+
+/// Pixel names files after the UTC time of the shot
+fn recognize_pixel(s: &str) -> Option<AppMatch> {
+    let caps = get_pattern_pixel().captures(s)?;
+    let millis: i64 = caps.get(7)?.as_str().parse().ok()?;
+    let dt = build_datetime_from_captures(&caps)? + chrono::Duration::milliseconds(millis);
+    Some(AppMatch {
+        app: SourceApp::Pixel,
+        time: ContainerTime::Utc(dt),
+        precision: TimePrecision::Second,
+    })
+}
+
+fn recognize_samsung(s: &str) -> Option<AppMatch> {
+    let caps = get_pattern_samsung().captures(s)?;
+    Some(AppMatch {
+        app: SourceApp::Samsung,
+        time: ContainerTime::Local(build_datetime_from_captures(&caps)?),
+        precision: TimePrecision::Second,
+    })
+}
+
+/// Whether a Unix timestamp lies in 1990-2100
+fn is_reasonable_epoch(seconds: i64) -> bool {
+    (631152000..=4102444800).contains(&seconds)
+}
+
 fn try_pattern_unix(s: &str) -> Option<NaiveDateTime> {
     let caps = get_pattern_unix().captures(s)?;
     let timestamp_str = caps.get(1)?.as_str();
@@ -196,7 +465,7 @@ fn try_pattern_unix(s: &str) -> Option<NaiveDateTime> {
     };
 
     // Validate reasonable timestamp range (1990-2100)
-    if !(631152000..=4102444800).contains(&timestamp) {
+    if !is_reasonable_epoch(timestamp) {
         return None;
     }
 
@@ -215,6 +484,18 @@ fn try_pattern_date_only(s: &str) -> Option<NaiveDateTime> {
     )
 }
 
+/// Build a datetime from the first six groups (year to second) of a match
+fn build_datetime_from_captures(caps: &regex::Captures) -> Option<NaiveDateTime> {
+    build_datetime(
+        caps.get(1)?.as_str(),
+        caps.get(2)?.as_str(),
+        caps.get(3)?.as_str(),
+        caps.get(4)?.as_str(),
+        caps.get(5)?.as_str(),
+        caps.get(6)?.as_str(),
+    )
+}
+
 fn build_datetime(
     year: &str,
     month: &str,
@@ -252,6 +533,10 @@ mod tests {
     use super::*;
     use chrono::{Datelike, Timelike};
 
+    fn local(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
     #[test]
     fn test_compact_format() {
         let dt = parse_filename_time("20240115_143000.jpg").unwrap();
@@ -289,6 +574,18 @@ mod tests {
         assert_eq!(dt.year(), 2024);
     }
 
+    #[test]
+    fn test_unix_timestamp_is_utc() {
+        assert!(matches!(
+            parse_filename_timestamp("photo_1705329000.jpg", &[]),
+            Some((ContainerTime::Utc(_), _))
+        ));
+        assert!(matches!(
+            parse_filename_timestamp("IMG_20240115_143000.jpg", &[]),
+            Some((ContainerTime::Local(_), _))
+        ));
+    }
+
     #[test]
     fn test_whatsapp_format() {
         let dt = parse_filename_time("IMG-20240115-WA0001.jpg").unwrap();
@@ -297,12 +594,191 @@ mod tests {
         assert_eq!(dt.day(), 15);
     }
 
+    #[test]
+    fn test_wechat_recognizer() {
+        let m = recognize_wechat("mmexport1634567890123").unwrap();
+        assert_eq!(m.app, SourceApp::WeChat);
+        let ContainerTime::Utc(dt) = m.time else {
+            panic!("WeChat times are UTC");
+        };
+        assert_eq!(dt.and_utc().timestamp_millis(), 1_634_567_890_123);
+        assert!(recognize_wechat("mmexport123").is_none());
+    }
+
+    #[test]
+    fn test_telegram_recognizer() {
+        let m = recognize_telegram("photo_2023-05-01_12-30-45").unwrap();
+        assert_eq!(m.app, SourceApp::Telegram);
+        assert_eq!(m.time, ContainerTime::Local(local("2023-05-01 12:30:45")));
+        assert!(recognize_telegram("video_2023-05-01_12-30-45").is_some());
+        assert!(recognize_telegram("my_photo_2023-05-01_12-30-45").is_none());
+    }
+
+    #[test]
+    fn test_signal_recognizer() {
+        let m = recognize_signal("signal-2023-05-01-123045").unwrap();
+        assert_eq!(m.app, SourceApp::Signal);
+        assert_eq!(m.time, ContainerTime::Local(local("2023-05-01 12:30:45")));
+        assert!(recognize_signal("signal-2023-05-01-12-30-45-123").is_some());
+    }
+
+    #[test]
+    fn test_pixel_recognizer() {
+        let m = recognize_pixel("PXL_20230501_123045123").unwrap();
+        assert_eq!(m.app, SourceApp::Pixel);
+        let ContainerTime::Utc(dt) = m.time else {
+            panic!("Pixel times are UTC");
+        };
+        assert_eq!(
+            dt.format("%Y-%m-%d %H:%M:%S%.3f").to_string(),
+            "2023-05-01 12:30:45.123"
+        );
+        assert!(recognize_pixel("PXL_20230501_123045123.NIGHT").is_some());
+    }
+
+    #[test]
+    fn test_samsung_recognizer() {
+        let m = recognize_samsung("20230501_123045(0)").unwrap();
+        assert_eq!(m.app, SourceApp::Samsung);
+        assert_eq!(m.time, ContainerTime::Local(local("2023-05-01 12:30:45")));
+        assert!(recognize_samsung("20230501_123045").is_none());
+        assert!(recognize_samsung("IMG_20230501_123045").is_none());
+    }
+
+    #[test]
+    fn test_recognize_app() {
+        let app = |filename| recognize_app(filename).map(|m| m.app);
+        assert_eq!(app("IMG-20240115-WA0001.jpg"), Some(SourceApp::WhatsApp));
+        assert_eq!(app("mmexport1634567890123.jpg"), Some(SourceApp::WeChat));
+        assert_eq!(app("PXL_20230501_123045123.mp4"), Some(SourceApp::Pixel));
+        assert_eq!(app("IMG_20230501_123045.jpg"), None);
+        assert_eq!(app("20230501_123045(1).jpg"), Some(SourceApp::Samsung));
+        // Generic names are dated but not attributed to an app
+        assert_eq!(app("20230501_123045.jpg"), None);
+        assert_eq!(
+            parse_filename_time("20230501_123045.jpg"),
+            Some(local("2023-05-01 12:30:45"))
+        );
+        assert_eq!(app("holiday.jpg"), None);
+
+        // App schemes take part in the regular filename parsing
+        assert!(matches!(
+            parse_filename_timestamp("PXL_20230501_123045123.jpg", &[]),
+            Some((ContainerTime::Utc(_), _))
+        ));
+    }
+
     #[test]
     fn test_separated_format() {
         let dt = parse_filename_time("2024-01-15_14-30-00.jpg").unwrap();
         assert_eq!(dt.year(), 2024);
     }
 
+    #[test]
+    fn test_custom_format_pattern() {
+        let patterns = [FilenamePattern::with_format(
+            "scanner",
+            "SCAN_%Y%m%d_%H%M%S",
+        )];
+        let (time, precision) =
+            parse_filename_timestamp("Album1_SCAN_19870623_101500.tif", &patterns).unwrap();
+        assert_eq!(time.naive().year(), 1987);
+        assert_eq!(time.naive().hour(), 10);
+        assert_eq!(precision, TimePrecision::Second);
+
+        let patterns = [FilenamePattern::with_format("date", "%d.%m.%Y")];
+        let dt = parse_filename_time_with("Party 24.12.2023.jpg", &patterns);
+        assert_eq!(dt.day(), 24);
+        assert_eq!(dt.hour(), 0);
+    }
+
+    #[test]
+    fn test_custom_regex_pattern() {
+        let patterns = [FilenamePattern::with_regex(
+            "dashcam",
+            r"^(?P<year>\d{2})(?P<month>\d{2})(?P<day>\d{2})-(?P<hour>\d{2})(?P<minute>\d{2})(?P<second>\d{2})-(?P<millis>\d{3})F$",
+        )];
+        let dt = parse_filename_time_with("240115-143000-250F.mp4", &patterns);
+        assert_eq!(dt.year(), 2024);
+        assert_eq!(dt.second(), 0);
+        assert_eq!(dt.nanosecond(), 250_000_000);
+
+        let patterns = [FilenamePattern::with_regex("epoch", r"cam(?P<epoch>\d+)")];
+        assert!(matches!(
+            parse_filename_timestamp("cam1705329000000.jpg", &patterns),
+            Some((ContainerTime::Utc(_), _))
+        ));
+    }
+
+    #[test]
+    fn test_custom_pattern_precedence() {
+        let mut pattern = FilenamePattern::with_format("ydm", "%Y%d%m_%H%M%S");
+        // The built-in compact pattern reads December 1st, the custom one January 12th
+        let name = "20241201_143000.jpg";
+        let dt = parse_filename_time_with(name, std::slice::from_ref(&pattern));
+        assert_eq!(dt.month(), 1);
+
+        pattern.precedence = PatternPrecedence::Last;
+        let dt = parse_filename_time_with(name, std::slice::from_ref(&pattern));
+        assert_eq!(dt.month(), 12);
+
+        // No built-in pattern accepts month 13, so the fallback applies
+        let dt = parse_filename_time_with("20241301_143000.jpg", &[pattern]);
+        assert_eq!((dt.month(), dt.day()), (1, 13));
+    }
+
+    #[test]
+    fn test_validate_custom_pattern() {
+        assert!(
+            FilenamePattern::with_format("ok", "%Y%m%d")
+                .validate()
+                .is_ok()
+        );
+        assert!(
+            FilenamePattern::with_format("bad", "%Q")
+                .validate()
+                .is_err()
+        );
+        assert!(
+            FilenamePattern::with_regex("ok", r"(?P<epoch>\d+)")
+                .validate()
+                .is_ok()
+        );
+        assert!(
+            FilenamePattern::with_regex("no groups", r"\d+")
+                .validate()
+                .is_err()
+        );
+        assert!(FilenamePattern::with_regex("bad", r"(").validate().is_err());
+    }
+
+    fn parse_filename_time_with(filename: &str, patterns: &[FilenamePattern]) -> NaiveDateTime {
+        parse_filename_timestamp(filename, patterns)
+            .unwrap()
+            .0
+            .naive()
+    }
+
+    #[test]
+    fn test_precision() {
+        let precision = |filename| parse_filename_timestamp(filename, &[]).map(|(_, p)| p);
+        assert_eq!(
+            precision("IMG_20240115_143000.jpg"),
+            Some(TimePrecision::Second)
+        );
+        assert_eq!(precision("Scan 20240115.jpg"), Some(TimePrecision::Day));
+        assert_eq!(
+            precision("IMG-20240115-WA0001.jpg"),
+            Some(TimePrecision::Day)
+        );
+
+        let patterns = [FilenamePattern::with_format("date", "%d.%m.%Y")];
+        assert_eq!(
+            parse_filename_timestamp("Party 24.12.2023.jpg", &patterns).map(|(_, p)| p),
+            Some(TimePrecision::Day)
+        );
+    }
+
     #[test]
     fn test_invalid_formats() {
         assert!(parse_filename_time("random_file.jpg").is_none());
@@ -0,0 +1,418 @@
+//! Native ISO-BMFF (MP4/MOV) creation time extraction
+//!
+//! Walks the box (atom) tree of MP4, MOV, M4V and 3GP files without
+//! spawning FFprobe. The following locations are checked, in priority order:
+//! 1. QuickTime `moov/meta` `keys` + `ilst` (`com.apple.quicktime.creationdate`)
+//! 2. `moov/mvhd` creation time
+//! 3. `moov/trak/mdia/mdhd` creation time of the first track that has one
+//!
+//! The Apple creation date carries the local wall-clock time and its offset,
+//! while `mvhd`/`mdhd` store seconds since 1904-01-01 in UTC. UTC values are
+//! paired with the recording location (`com.apple.quicktime.location.ISO6709`
+//! or `udta/©xyz`) when the file has one.
+
+use crate::error::{Error, Result};
+use crate::time::datetime::parse_video_datetime;
+use crate::time::timezone::{GeoLocation, parse_iso6709};
+use crate::time::video::ContainerTime;
+use chrono::{DateTime, NaiveDateTime};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+use tracing::trace;
+
+/// Seconds between 1904-01-01 (QuickTime epoch) and 1970-01-01 (Unix epoch)
+const QUICKTIME_EPOCH_OFFSET: i64 = 2_082_844_800;
+
+/// Upper bound for the size of a `moov` box we are willing to load into memory
+const MAX_MOOV_SIZE: u64 = 64 * 1024 * 1024;
+
+/// QuickTime metadata key holding the local creation date
+const APPLE_CREATION_DATE_KEY: &[u8] = b"com.apple.quicktime.creationdate";
+
+/// QuickTime metadata key holding the ISO 6709 recording location
+const APPLE_LOCATION_KEY: &[u8] = b"com.apple.quicktime.location.ISO6709";
+
+/// User data box holding the ISO 6709 recording location
+const UDTA_LOCATION_TYPE: &[u8; 4] = b"\xa9xyz";
+
+/// Box types that may appear at the top level of an ISO-BMFF/QuickTime file
+const TOP_LEVEL_TYPES: &[&[u8; 4]] = &[
+    b"ftyp", b"moov", b"mdat", b"free", b"skip", b"wide", b"uuid", b"pnot", b"meta",
+];
+
+/// Extract creation time from an ISO-BMFF/QuickTime container
+pub fn extract_isobmff_time(path: &Path) -> Result<ContainerTime> {
+    let file = File::open(path)?;
+    let mut reader = BufReader::new(file);
+
+    let moov = read_moov(&mut reader).map_err(|message| Error::VideoMetadata {
+        path: path.to_path_buf(),
+        message,
+    })?;
+
+    parse_moov(&moov).ok_or_else(|| Error::VideoMetadata {
+        path: path.to_path_buf(),
+        message: "No creation time found in ISO-BMFF boxes".to_string(),
+    })
+}
+
+/// Locate the top-level `moov` box and return its payload
+fn read_moov<R: Read + Seek>(reader: &mut R) -> std::result::Result<Vec<u8>, String> {
+    let file_size = reader.seek(SeekFrom::End(0)).map_err(|e| e.to_string())?;
+    reader.seek(SeekFrom::Start(0)).map_err(|e| e.to_string())?;
+
+    let mut offset = 0u64;
+    let mut first = true;
+
+    while offset + 8 <= file_size {
+        let mut header = [0u8; 8];
+        reader.read_exact(&mut header).map_err(|e| e.to_string())?;
+        let box_type: [u8; 4] = header[4..8].try_into().unwrap();
+
+        if first && !TOP_LEVEL_TYPES.contains(&&box_type) {
+            return Err("Not an ISO-BMFF container".to_string());
+        }
+        first = false;
+
+        let mut header_len = 8u64;
+        let box_size = match u32::from_be_bytes(header[0..4].try_into().unwrap()) {
+            0 => file_size - offset,
+            1 => {
+                let mut large = [0u8; 8];
+                reader.read_exact(&mut large).map_err(|e| e.to_string())?;
+                header_len = 16;
+                u64::from_be_bytes(large)
+            }
+            size => size as u64,
+        };
+
+        let end = offset
+            .checked_add(box_size)
+            .filter(|end| box_size >= header_len && *end <= file_size)
+            .ok_or_else(|| format!("Invalid box size {} at offset {}", box_size, offset))?;
+
+        if &box_type == b"moov" {
+            let payload_size = box_size - header_len;
+            if payload_size > MAX_MOOV_SIZE {
+                return Err(format!("moov box too large ({} bytes)", payload_size));
+            }
+            let mut payload = vec![0u8; payload_size as usize];
+            reader.read_exact(&mut payload).map_err(|e| e.to_string())?;
+            return Ok(payload);
+        }
+
+        offset = end;
+        reader
+            .seek(SeekFrom::Start(offset))
+            .map_err(|e| e.to_string())?;
+    }
+
+    Err("No moov box found".to_string())
+}
+
+/// Iterate over the child boxes contained in `data`
+fn child_boxes(data: &[u8]) -> impl Iterator<Item = ([u8; 4], &[u8])> {
+    let mut pos = 0usize;
+    std::iter::from_fn(move || {
+        if pos + 8 > data.len() {
+            return None;
+        }
+        let size32 = u32::from_be_bytes(data[pos..pos + 4].try_into().unwrap()) as usize;
+        let box_type: [u8; 4] = data[pos + 4..pos + 8].try_into().unwrap();
+        let (header_len, size) = match size32 {
+            0 => (8, data.len() - pos),
+            1 => {
+                if pos + 16 > data.len() {
+                    return None;
+                }
+                let large = u64::from_be_bytes(data[pos + 8..pos + 16].try_into().unwrap());
+                (16, usize::try_from(large).ok()?)
+            }
+            size => (8, size),
+        };
+        let end = pos
+            .checked_add(size)
+            .filter(|end| size >= header_len && *end <= data.len())?;
+        let payload = &data[pos + header_len..end];
+        pos = end;
+        Some((box_type, payload))
+    })
+}
+
+/// Find the first child box of the given type
+fn find_box<'a>(data: &'a [u8], box_type: &[u8; 4]) -> Option<&'a [u8]> {
+    child_boxes(data)
+        .find(|(t, _)| t == box_type)
+        .map(|(_, payload)| payload)
+}
+
+/// Parse the contents of a `moov` box
+fn parse_moov(moov: &[u8]) -> Option<ContainerTime> {
+    // QuickTime metadata: moov/meta, or moov/udta/meta on some encoders
+    let udta = find_box(moov, b"udta");
+    let meta = find_box(moov, b"meta").or_else(|| udta.and_then(|udta| find_box(udta, b"meta")));
+    if let Some(meta) = meta
+        && let Some(local) =
+            find_quicktime_value(meta, APPLE_CREATION_DATE_KEY).and_then(parse_apple_date)
+    {
+        trace!("Found com.apple.quicktime.creationdate");
+        return Some(local);
+    }
+
+    let location = meta
+        .and_then(|meta| find_quicktime_value(meta, APPLE_LOCATION_KEY))
+        .and_then(parse_iso6709)
+        .or_else(|| udta.and_then(parse_udta_location));
+    let with_location = |utc| match location {
+        Some(location) => ContainerTime::Located(utc, location),
+        None => ContainerTime::Utc(utc),
+    };
+
+    if let Some(mvhd) = find_box(moov, b"mvhd")
+        && let Some(utc) = parse_header_creation_time(mvhd)
+    {
+        trace!("Found mvhd creation time");
+        return Some(with_location(utc));
+    }
+
+    for (box_type, trak) in child_boxes(moov) {
+        if &box_type != b"trak" {
+            continue;
+        }
+        if let Some(mdhd) = find_box(trak, b"mdia").and_then(|mdia| find_box(mdia, b"mdhd"))
+            && let Some(utc) = parse_header_creation_time(mdhd)
+        {
+            trace!("Found mdhd creation time");
+            return Some(with_location(utc));
+        }
+    }
+
+    None
+}
+
+/// Parse the creation time field of an `mvhd` or `mdhd` full box
+fn parse_header_creation_time(data: &[u8]) -> Option<NaiveDateTime> {
+    let version = *data.first()?;
+    let seconds = if version == 1 {
+        u64::from_be_bytes(data.get(4..12)?.try_into().ok()?)
+    } else {
+        u32::from_be_bytes(data.get(4..8)?.try_into().ok()?) as u64
+    };
+
+    // Zero means the muxer did not set a creation time
+    if seconds == 0 {
+        return None;
+    }
+
+    let unix = i64::try_from(seconds).ok()? - QUICKTIME_EPOCH_OFFSET;
+    DateTime::from_timestamp(unix, 0).map(|dt| dt.naive_utc())
+}
+
+/// Look up the string value of a key in a QuickTime `meta` box
+fn find_quicktime_value<'a>(meta: &'a [u8], key: &[u8]) -> Option<&'a str> {
+    // ISO-style meta is a full box with 4 bytes of version/flags before its
+    // children, QuickTime-style meta is not. Detect by looking for `hdlr`.
+    let children = if meta.get(4..8) == Some(b"hdlr") {
+        meta
+    } else {
+        meta.get(4..)?
+    };
+
+    let keys = find_box(children, b"keys")?;
+    let ilst = find_box(children, b"ilst")?;
+
+    // keys: version/flags(4), entry_count(4), then [size(4), namespace(4), name]
+    let entry_count = u32::from_be_bytes(keys.get(4..8)?.try_into().ok()?);
+    let mut pos = 8usize;
+    let mut key_index = None;
+    for index in 1..=entry_count {
+        let size = u32::from_be_bytes(keys.get(pos..pos + 4)?.try_into().ok()?) as usize;
+        if size < 8 {
+            return None;
+        }
+        let name = keys.get(pos + 8..pos + size)?;
+        if name == key {
+            key_index = Some(index);
+            break;
+        }
+        pos += size;
+    }
+    let key_index = key_index?;
+
+    // ilst items use the 1-based key index as their box type
+    let item = child_boxes(ilst)
+        .find(|(t, _)| u32::from_be_bytes(*t) == key_index)
+        .map(|(_, payload)| payload)?;
+    let data = find_box(item, b"data")?;
+    // data: type indicator(4), locale(4), value
+    std::str::from_utf8(data.get(8..)?).ok()
+}
+
+/// Parse the `©xyz` recording location of a `udta` box
+fn parse_udta_location(udta: &[u8]) -> Option<GeoLocation> {
+    let xyz = find_box(udta, UDTA_LOCATION_TYPE)?;
+    // ©xyz: string length(2), language(2), ISO 6709 string
+    let len = u16::from_be_bytes(xyz.get(0..2)?.try_into().ok()?) as usize;
+    let value = std::str::from_utf8(xyz.get(4..4 + len)?).ok()?;
+    parse_iso6709(value)
+}
+
+/// Parse an Apple creation date, keeping the local wall-clock time
+fn parse_apple_date(value: &str) -> Option<ContainerTime> {
+    let value = value.trim_end_matches('\0').trim();
+    for format in ["%Y-%m-%dT%H:%M:%S%z", "%Y-%m-%dT%H:%M:%S%.f%z"] {
+        if let Ok(dt) = DateTime::parse_from_str(value, format) {
+            return Some(ContainerTime::Local(dt.naive_local()));
+        }
+    }
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Some(ContainerTime::Local(dt.naive_local()));
+    }
+    // Without an offset the value is treated like any other UTC video tag
+    parse_video_datetime(value).map(ContainerTime::Utc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Datelike, Timelike};
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn make_box(box_type: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut out = ((payload.len() + 8) as u32).to_be_bytes().to_vec();
+        out.extend_from_slice(box_type);
+        out.extend_from_slice(payload);
+        out
+    }
+
+    fn make_mvhd(unix_seconds: i64) -> Vec<u8> {
+        let mut payload = vec![0u8; 4];
+        let qt = (unix_seconds + QUICKTIME_EPOCH_OFFSET) as u32;
+        payload.extend_from_slice(&qt.to_be_bytes());
+        payload.extend_from_slice(&qt.to_be_bytes());
+        payload.extend_from_slice(&[0u8; 88]);
+        make_box(b"mvhd", &payload)
+    }
+
+    fn make_apple_meta(value: &str) -> Vec<u8> {
+        let mut key_entry = ((APPLE_CREATION_DATE_KEY.len() + 8) as u32)
+            .to_be_bytes()
+            .to_vec();
+        key_entry.extend_from_slice(b"mdta");
+        key_entry.extend_from_slice(APPLE_CREATION_DATE_KEY);
+        let mut keys_payload = vec![0u8; 4];
+        keys_payload.extend_from_slice(&1u32.to_be_bytes());
+        keys_payload.extend_from_slice(&key_entry);
+
+        let mut data_payload = 1u32.to_be_bytes().to_vec();
+        data_payload.extend_from_slice(&[0u8; 4]);
+        data_payload.extend_from_slice(value.as_bytes());
+        let item = make_box(&1u32.to_be_bytes(), &make_box(b"data", &data_payload));
+
+        let mut meta_payload = make_box(b"hdlr", &[0u8; 24]);
+        meta_payload.extend(make_box(b"keys", &keys_payload));
+        meta_payload.extend(make_box(b"ilst", &item));
+        make_box(b"meta", &meta_payload)
+    }
+
+    fn write_file(moov_children: &[u8]) -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(&make_box(b"ftyp", b"qt  \0\0\0\0qt  "))
+            .unwrap();
+        file.write_all(&make_box(b"mdat", &[0u8; 32])).unwrap();
+        file.write_all(&make_box(b"moov", moov_children)).unwrap();
+        file.flush().unwrap();
+        file
+    }
+
+    #[test]
+    fn test_mvhd_creation_time() {
+        // 2024-01-15 14:30:00 UTC
+        let file = write_file(&make_mvhd(1_705_329_000));
+        match extract_isobmff_time(file.path()).unwrap() {
+            ContainerTime::Utc(dt) => {
+                assert_eq!(dt.year(), 2024);
+                assert_eq!(dt.hour(), 14);
+                assert_eq!(dt.minute(), 30);
+            }
+            other => panic!("expected UTC time, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_apple_creation_date_preferred() {
+        let mut moov = make_mvhd(1_705_329_000);
+        moov.extend(make_apple_meta("2024-01-15T22:30:00+0800"));
+        let file = write_file(&moov);
+        match extract_isobmff_time(file.path()).unwrap() {
+            ContainerTime::Local(dt) => {
+                assert_eq!(dt.day(), 15);
+                assert_eq!(dt.hour(), 22);
+            }
+            other => panic!("expected local time, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_udta_location_paired_with_utc() {
+        let value = b"+52.5200+013.4050/";
+        let mut xyz = (value.len() as u16).to_be_bytes().to_vec();
+        xyz.extend_from_slice(&[0x15, 0xc7]);
+        xyz.extend_from_slice(value);
+
+        let mut moov = make_mvhd(1_705_329_000);
+        moov.extend(make_box(b"udta", &make_box(UDTA_LOCATION_TYPE, &xyz)));
+        let file = write_file(&moov);
+        match extract_isobmff_time(file.path()).unwrap() {
+            ContainerTime::Located(dt, location) => {
+                assert_eq!(dt.hour(), 14);
+                assert!((location.latitude - 52.52).abs() < 1e-9);
+                assert!((location.longitude - 13.405).abs() < 1e-9);
+            }
+            other => panic!("expected located time, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_zero_creation_time_falls_back_to_mdhd() {
+        let mut moov = make_box(b"mvhd", &[0u8; 100]);
+        let mdhd = make_mvhd(1_705_329_000);
+        let mdhd = make_box(b"mdhd", &mdhd[8..]);
+        moov.extend(make_box(b"trak", &make_box(b"mdia", &mdhd)));
+        let file = write_file(&moov);
+        assert!(matches!(
+            extract_isobmff_time(file.path()).unwrap(),
+            ContainerTime::Utc(_)
+        ));
+    }
+
+    #[test]
+    fn test_oversized_largesize() {
+        // A 64-bit size that would wrap the offset around to the file start
+        let mut large = 1u32.to_be_bytes().to_vec();
+        large.extend_from_slice(b"mdat");
+        large.extend_from_slice(&(u64::MAX - 15).to_be_bytes());
+
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(&make_box(b"ftyp", b"qt  \0\0\0\0qt  "))
+            .unwrap();
+        file.write_all(&large).unwrap();
+        file.write_all(&make_box(b"moov", &make_mvhd(1_705_329_000)))
+            .unwrap();
+        file.flush().unwrap();
+        assert!(extract_isobmff_time(file.path()).is_err());
+
+        large[8..].copy_from_slice(&u64::MAX.to_be_bytes());
+        assert_eq!(child_boxes(&large).count(), 0);
+    }
+
+    #[test]
+    fn test_not_isobmff() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(b"RIFF\0\0\0\0AVI LIST").unwrap();
+        file.flush().unwrap();
+        assert!(extract_isobmff_time(file.path()).is_err());
+    }
+}
@@ -0,0 +1,196 @@
+//! Native Matroska/WebM creation time extraction
+//!
+//! Reads the `Segment/Info/DateUTC` EBML element, a signed count of
+//! nanoseconds since 2001-01-01T00:00:00 UTC.
+
+use crate::error::{Error, Result};
+use crate::time::video::ContainerTime;
+use chrono::{DateTime, NaiveDateTime};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+use tracing::trace;
+
+/// EBML header element ID
+const ID_EBML: u32 = 0x1A45_DFA3;
+/// Segment element ID
+const ID_SEGMENT: u32 = 0x1853_8067;
+/// Segment Info element ID
+const ID_INFO: u32 = 0x1549_A966;
+/// DateUTC element ID
+const ID_DATE_UTC: u32 = 0x4461;
+/// Cluster element ID (media data starts here)
+const ID_CLUSTER: u32 = 0x1F43_B675;
+
+/// Unix timestamp of the Matroska epoch (2001-01-01T00:00:00 UTC)
+const MATROSKA_EPOCH: i64 = 978_307_200;
+
+/// Upper bound for the size of an Info element we are willing to load
+const MAX_INFO_SIZE: u64 = 1024 * 1024;
+
+/// Extract creation time from a Matroska/WebM container
+pub fn extract_matroska_time(path: &Path) -> Result<ContainerTime> {
+    let file = File::open(path)?;
+    let mut reader = BufReader::new(file);
+
+    let make_error = |message: &str| Error::VideoMetadata {
+        path: path.to_path_buf(),
+        message: message.to_string(),
+    };
+
+    // EBML header
+    let (id, size) =
+        read_element_header(&mut reader).ok_or_else(|| make_error("Not an EBML file"))?;
+    if id != ID_EBML {
+        return Err(make_error("Not a Matroska container"));
+    }
+    let size = size.ok_or_else(|| make_error("Invalid EBML header size"))?;
+    reader
+        .seek(SeekFrom::Current(size as i64))
+        .map_err(|e| make_error(&e.to_string()))?;
+
+    // Segment (its size may be unknown for live recordings)
+    let (id, _) = read_element_header(&mut reader).ok_or_else(|| make_error("No Segment found"))?;
+    if id != ID_SEGMENT {
+        return Err(make_error("No Segment found"));
+    }
+
+    let info = read_info(&mut reader).ok_or_else(|| make_error("No Segment Info found"))?;
+    let time = parse_date_utc(&info).ok_or_else(|| make_error("No DateUTC in Segment Info"))?;
+
+    trace!(?path, "Found Matroska DateUTC");
+    Ok(ContainerTime::Utc(time))
+}
+
+/// Walk the Segment children until the Info element and return its payload
+fn read_info<R: Read + Seek>(reader: &mut R) -> Option<Vec<u8>> {
+    loop {
+        let (id, size) = read_element_header(reader)?;
+        if id == ID_INFO {
+            let size = size?;
+            if size > MAX_INFO_SIZE {
+                return None;
+            }
+            let mut payload = vec![0u8; size as usize];
+            reader.read_exact(&mut payload).ok()?;
+            return Some(payload);
+        }
+        // Info precedes the clusters in any sane file
+        if id == ID_CLUSTER {
+            return None;
+        }
+        reader
+            .seek(SeekFrom::Current(i64::try_from(size?).ok()?))
+            .ok()?;
+    }
+}
+
+/// Find DateUTC among the children of the Info element
+fn parse_date_utc(info: &[u8]) -> Option<NaiveDateTime> {
+    let mut cursor = info;
+    while !cursor.is_empty() {
+        let (id, size) = read_element_header(&mut cursor)?;
+        let size = usize::try_from(size?).ok()?;
+        if size > cursor.len() {
+            return None;
+        }
+        let (payload, rest) = cursor.split_at(size);
+        if id == ID_DATE_UTC && size == 8 {
+            let nanos = i64::from_be_bytes(payload.try_into().ok()?);
+            let secs = MATROSKA_EPOCH + nanos.div_euclid(1_000_000_000);
+            let subsec = nanos.rem_euclid(1_000_000_000) as u32;
+            return DateTime::from_timestamp(secs, subsec).map(|dt| dt.naive_utc());
+        }
+        cursor = rest;
+    }
+    None
+}
+
+/// Read an element ID and data size; the size is `None` when unknown
+fn read_element_header<R: Read>(reader: &mut R) -> Option<(u32, Option<u64>)> {
+    let (id, _) = read_vint(reader, true)?;
+    let (size, len) = read_vint(reader, false)?;
+    // All value bits set means "unknown size"
+    let unknown = size == (1u64 << (7 * len)) - 1;
+    Some((
+        u32::try_from(id).ok()?,
+        if unknown { None } else { Some(size) },
+    ))
+}
+
+/// Read an EBML variable-length integer, keeping the length marker for IDs
+fn read_vint<R: Read>(reader: &mut R, keep_marker: bool) -> Option<(u64, u32)> {
+    let mut first = [0u8; 1];
+    reader.read_exact(&mut first).ok()?;
+    let len = first[0].leading_zeros() + 1;
+    if len > 8 {
+        return None;
+    }
+
+    let mut value = if keep_marker {
+        first[0] as u64
+    } else {
+        first[0] as u64 & (0xFF >> len)
+    };
+    for _ in 1..len {
+        let mut byte = [0u8; 1];
+        reader.read_exact(&mut byte).ok()?;
+        value = (value << 8) | byte[0] as u64;
+    }
+    Some((value, len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Datelike, Timelike};
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn make_element(id: &[u8], payload: &[u8]) -> Vec<u8> {
+        let mut out = id.to_vec();
+        // 8-byte size vint
+        out.push(0x01);
+        out.extend_from_slice(&(payload.len() as u64).to_be_bytes()[1..]);
+        out.extend_from_slice(payload);
+        out
+    }
+
+    #[test]
+    fn test_date_utc() {
+        // 2024-01-15 14:30:00 UTC
+        let nanos = (1_705_329_000 - MATROSKA_EPOCH) * 1_000_000_000;
+        let mut info = make_element(&[0x2A, 0xD7, 0xB1], &1_000_000u32.to_be_bytes());
+        info.extend(make_element(&[0x44, 0x61], &nanos.to_be_bytes()));
+
+        let mut segment = make_element(&[0x11, 0x4D, 0x9B, 0x74], &[0u8; 10]);
+        segment.extend(make_element(&[0x15, 0x49, 0xA9, 0x66], &info));
+
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(&make_element(
+            &[0x1A, 0x45, 0xDF, 0xA3],
+            b"\x42\x82\x84webm",
+        ))
+        .unwrap();
+        // Segment with unknown size
+        file.write_all(&[0x18, 0x53, 0x80, 0x67, 0xFF]).unwrap();
+        file.write_all(&segment).unwrap();
+        file.flush().unwrap();
+
+        let ContainerTime::Utc(dt) = extract_matroska_time(file.path()).unwrap() else {
+            panic!("expected UTC time");
+        };
+        assert_eq!(dt.year(), 2024);
+        assert_eq!(dt.day(), 15);
+        assert_eq!(dt.hour(), 14);
+        assert_eq!(dt.minute(), 30);
+    }
+
+    #[test]
+    fn test_not_matroska() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(b"RIFF\0\0\0\0AVI ").unwrap();
+        file.flush().unwrap();
+        assert!(extract_matroska_time(file.path()).is_err());
+    }
+}
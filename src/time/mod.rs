@@ -2,32 +2,125 @@
 //!
 //! This module provides functionality to extract creation timestamps from:
 //! - EXIF metadata in images (JPEG, HEIF, RAW formats)
+//! - XMP sidecars (`.xmp` files next to the media file)
+//! - Native container metadata (MP4/MOV, AVI, Matroska/WebM, AVCHD)
 //! - Video metadata via FFprobe
+//! - Google Takeout JSON metadata
 //! - Filename patterns
-//! - File system modification time
+//! - Dated directory names
+//! - File system modification or birth time
+//!
+//! UTC timestamps that come with GPS coordinates are converted to the local
+//! time of the capture location using an embedded timezone table.
 
+pub mod audit;
+pub mod avchd;
+pub mod directory;
 pub mod exif;
+pub mod export;
 pub mod filename;
+pub mod isobmff;
+pub mod matroska;
+pub mod riff;
+pub mod sequence;
+pub mod timezone;
 pub mod video;
+pub mod xmp;
 
-use crate::config::Config;
-use crate::error::Result;
-use chrono::NaiveDateTime;
+use crate::config::{Config, ConflictResolution, FileTimeKind, TimeSourceKind};
+use crate::error::{Error, Result};
+use crate::time::exif::ExifTime;
+use crate::time::timezone::ZoneRules;
+use crate::time::video::ContainerTime;
+use chrono::{Duration, FixedOffset, NaiveDateTime, Timelike};
+use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use tracing::{debug, warn};
 
 /// Source of the extracted timestamp
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TimeSource {
     /// Extracted from EXIF metadata
     Exif,
+    /// Extracted from an XMP sidecar
+    XmpSidecar,
+    /// Extracted from MP4/MOV boxes by the native ISO-BMFF reader
+    IsoBmff,
+    /// Extracted from AVI `IDIT`/`strd` chunks by the native RIFF reader
+    Riff,
+    /// Extracted from the Matroska/WebM `DateUTC` element
+    Matroska,
+    /// Extracted from AVCHD (MTS/M2TS) MDPM user data
+    Avchd,
     /// Extracted from video metadata via FFprobe
     VideoMetadata,
+    /// Extracted from Google Takeout JSON metadata
+    ExportMetadata,
     /// Parsed from filename
     Filename,
+    /// Parsed from the name of an enclosing directory
+    DirectoryName,
     /// From file system modification time
     FileSystem,
+    /// From file system birth (creation) time
+    FileCreated,
+    /// Interpolated from dated files of the same camera sequence
+    Interpolated,
+}
+
+impl TimeSource {
+    /// Configurable time source that produces this kind of timestamp
+    ///
+    /// Interpolated times come from other files, not from a time source.
+    pub fn kind(&self) -> Option<TimeSourceKind> {
+        let kind = match self {
+            TimeSource::Exif => TimeSourceKind::Exif,
+            TimeSource::XmpSidecar => TimeSourceKind::Xmp,
+            TimeSource::IsoBmff | TimeSource::Riff | TimeSource::Matroska | TimeSource::Avchd => {
+                TimeSourceKind::Container
+            }
+            TimeSource::VideoMetadata => TimeSourceKind::Ffprobe,
+            TimeSource::ExportMetadata => TimeSourceKind::Export,
+            TimeSource::Filename => TimeSourceKind::Filename,
+            TimeSource::DirectoryName => TimeSourceKind::Directory,
+            TimeSource::FileSystem | TimeSource::FileCreated => TimeSourceKind::Mtime,
+            TimeSource::Interpolated => return None,
+        };
+        Some(kind)
+    }
+
+    /// Whether the timestamp comes from the file system rather than the file
+    pub fn is_file_system(&self) -> bool {
+        matches!(self, TimeSource::FileSystem | TimeSource::FileCreated)
+    }
+}
+
+/// How precisely a timestamp is known, from least to most precise
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum TimePrecision {
+    /// Estimated rather than read, e.g. interpolated or from a year folder
+    Guessed,
+    /// Date only, the time is midnight
+    Day,
+    /// Hours and minutes, the seconds are zero
+    Minute,
+    /// Exact to the second or better
+    Second,
+}
+
+impl TimePrecision {
+    /// How much later than the timestamp the actual time may be
+    ///
+    /// None when the timestamp is only an estimate.
+    pub fn uncertainty(&self) -> Option<Duration> {
+        match self {
+            TimePrecision::Guessed => None,
+            TimePrecision::Day => Some(Duration::days(1) - Duration::seconds(1)),
+            TimePrecision::Minute => Some(Duration::seconds(59)),
+            TimePrecision::Second => Some(Duration::zero()),
+        }
+    }
 }
 
 /// Result of timestamp extraction
@@ -37,11 +130,43 @@ pub struct ExtractedTime {
     pub timestamp: NaiveDateTime,
     /// Source of the timestamp
     pub source: TimeSource,
+    /// How precisely `timestamp` is known
+    pub precision: TimePrecision,
+    /// UTC offset of `timestamp`, when the source records one
+    pub offset: Option<FixedOffset>,
+    /// Camera clock correction already applied to `timestamp`
+    pub clock_correction: Option<Duration>,
 }
 
+impl ExtractedTime {
+    /// The timestamp converted to UTC, if its offset is known
+    pub fn utc(&self) -> Option<NaiveDateTime> {
+        self.offset.map(|offset| {
+            self.timestamp - chrono::Duration::seconds(offset.local_minus_utc() as i64)
+        })
+    }
+
+    /// The latest the actual time can be given the precision, if it is known
+    pub fn latest_possible(&self) -> Option<NaiveDateTime> {
+        self.precision
+            .uncertainty()
+            .map(|uncertainty| self.timestamp + uncertainty)
+    }
+}
+
+/// Native container parsers, tried in order before falling back to FFprobe
+type NativeVideoParser = fn(&Path) -> Result<ContainerTime>;
+const NATIVE_VIDEO_PARSERS: &[(NativeVideoParser, TimeSource)] = &[
+    (isobmff::extract_isobmff_time, TimeSource::IsoBmff),
+    (riff::extract_riff_time, TimeSource::Riff),
+    (matroska::extract_matroska_time, TimeSource::Matroska),
+    (avchd::extract_avchd_time, TimeSource::Avchd),
+];
+
 /// Unified datetime parsing utilities
 pub mod datetime {
-    use chrono::{DateTime, NaiveDateTime};
+    use chrono::format::{Parsed, StrftimeItems, parse_and_remainder};
+    use chrono::{DateTime, NaiveDate, NaiveDateTime};
 
     /// Common ISO 8601 formats for video metadata
     const ISO8601_FORMATS: &[&str] = &[
@@ -66,6 +191,23 @@ pub mod datetime {
         "%Y/%m/%d %H:%M:%S",
     ];
 
+    /// Parse a date at the start of `s` with the first matching format
+    ///
+    /// Formats may leave out the day or the month, which then default to 1.
+    /// Returns the date and the text after it.
+    pub fn parse_date_prefix<'a>(s: &'a str, formats: &[&str]) -> Option<(NaiveDate, &'a str)> {
+        formats.iter().find_map(|format| {
+            let mut parsed = Parsed::new();
+            let rest = parse_and_remainder(&mut parsed, s, StrftimeItems::new(format)).ok()?;
+            let date = NaiveDate::from_ymd_opt(
+                parsed.year()?,
+                parsed.month().unwrap_or(1),
+                parsed.day().unwrap_or(1),
+            )?;
+            Some((date, rest))
+        })
+    }
+
     /// Parse datetime string with multiple format attempts
     ///
     /// Returns `Some(NaiveDateTime)` if parsing succeeds, `None` otherwise.
@@ -122,73 +264,611 @@ pub mod datetime {
 
 /// Extract creation time from a media file using multiple strategies
 ///
-/// The extraction follows this priority:
+/// Sources are tried in the order configured for the file (see
+/// [`Config::time_sources_for`]). The default order is:
 /// 1. EXIF metadata (for images)
-/// 2. Video metadata via FFprobe (for videos)
-/// 3. Filename parsing
-/// 4. File system modification time
+/// 2. XMP sidecar
+/// 3. Native container metadata (for MP4/MOV, AVI, Matroska and AVCHD videos)
+/// 4. Video metadata via FFprobe (for other videos)
+/// 5. Google Takeout JSON metadata
+/// 6. Filename parsing
+/// 7. Dated directory names
+/// 8. File system modification or birth time (see [`Config::file_time`])
+///
+/// Sources that don't apply to the file type are skipped, and timestamps
+/// outside [`Config::plausibility`] fall through to the next source.
+///
+/// With a conflict resolution other than `first`, every source is run and
+/// the result is chosen as described in [`audit`]. The matching camera clock
+/// correction, if any, is applied to the chosen timestamp.
 pub fn extract_time(path: &Path, config: &Config) -> Result<ExtractedTime> {
-    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    let time = match config.conflict_resolution {
+        ConflictResolution::First => time_candidates(path, config).next(),
+        _ => audit::TimeAudit::run(path, config).into_chosen(),
+    };
 
-    // Try EXIF for images
-    if config.is_image(ext) {
-        if let Ok(time) = exif::extract_exif_time(path) {
-            debug!(?path, "Extracted time from EXIF");
-            return Ok(ExtractedTime {
-                timestamp: time,
-                source: TimeSource::Exif,
-            });
-        }
-        debug!(?path, "No EXIF time found, trying other methods");
+    time.map(|time| correct_camera_clock(path, config, time))
+        .ok_or_else(|| Error::TimestampParse {
+            source_info: path.display().to_string(),
+            message: "No configured time source produced a timestamp".to_string(),
+        })
+}
+
+/// Apply the configured clock correction for the camera that took a photo
+///
+/// Only timestamps written by the camera are corrected, not the file system
+/// time.
+fn correct_camera_clock(path: &Path, config: &Config, mut time: ExtractedTime) -> ExtractedTime {
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    if config.camera_clock_offsets.is_empty()
+        || time.source.is_file_system()
+        || !config.is_image(ext)
+    {
+        return time;
     }
 
-    // Try video metadata for videos
-    if config.is_video(ext) {
-        if let Ok(time) = video::extract_video_time(path) {
-            debug!(?path, "Extracted time from video metadata");
-            return Ok(ExtractedTime {
-                timestamp: time,
-                source: TimeSource::VideoMetadata,
-            });
-        }
-        debug!(?path, "No video metadata time found, trying other methods");
+    let Ok(camera) = exif::extract_camera_info(path) else {
+        return time;
+    };
+    if let Some(correction) = config.camera_clock_correction(&camera, time.timestamp) {
+        debug!(
+            ?path,
+            ?camera,
+            correction = %correction,
+            "Applying camera clock correction"
+        );
+        time.timestamp += correction;
+        time.clock_correction = Some(correction);
     }
+    time
+}
+
+/// Lazily run the time sources configured for a file, in order, yielding
+/// each plausible timestamp
+pub fn time_candidates<'a>(
+    path: &'a Path,
+    config: &'a Config,
+) -> impl Iterator<Item = ExtractedTime> + 'a {
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    let now = chrono::Local::now().naive_local();
+
+    config
+        .time_sources_for(path)
+        .iter()
+        .filter_map(move |kind| {
+            let time = match kind {
+                TimeSourceKind::Exif if config.is_image(ext) => extract_from_exif(path, config),
+                TimeSourceKind::Xmp => extract_from_xmp(path),
+                TimeSourceKind::Container if config.is_video(ext) => {
+                    extract_from_container(path, config)
+                }
+                TimeSourceKind::Ffprobe if config.is_video(ext) => {
+                    extract_from_ffprobe(path, config)
+                }
+                TimeSourceKind::Export => extract_from_export(path, config),
+                TimeSourceKind::Filename => extract_from_filename(path, config),
+                TimeSourceKind::Directory => extract_from_directory(path, config),
+                TimeSourceKind::Mtime => extract_from_file_time(path, config),
+                _ => return None,
+            };
+            let Some(time) = time else {
+                debug!(?path, source = kind.name(), "No time found");
+                return None;
+            };
+            if !config.plausibility.accepts(time.timestamp, now) {
+                warn!(
+                    ?path,
+                    source = kind.name(),
+                    timestamp = %time.timestamp,
+                    "Rejected implausible timestamp"
+                );
+                return None;
+            }
+            Some(time)
+        })
+}
+
+/// Try EXIF metadata
+fn extract_from_exif(path: &Path, config: &Config) -> Option<ExtractedTime> {
+    let time = exif::extract_exif_time(path).ok()?;
+    debug!(?path, "Extracted time from EXIF");
+    let (timestamp, offset) = resolve_exif_time(&time, config.zone_rules());
+    Some(ExtractedTime {
+        timestamp,
+        source: TimeSource::Exif,
+        precision: TimePrecision::Second,
+        offset,
+        clock_correction: None,
+    })
+}
+
+/// Try the XMP sidecar
+fn extract_from_xmp(path: &Path) -> Option<ExtractedTime> {
+    let (timestamp, offset, precision) = xmp::extract_xmp_time(path).ok()?;
+    debug!(?path, "Extracted time from XMP sidecar");
+    Some(ExtractedTime {
+        timestamp,
+        source: TimeSource::XmpSidecar,
+        precision,
+        offset,
+        clock_correction: None,
+    })
+}
+
+/// Try the native container parsers
+fn extract_from_container(path: &Path, config: &Config) -> Option<ExtractedTime> {
+    NATIVE_VIDEO_PARSERS.iter().find_map(|(parser, source)| {
+        let time = parser(path).ok()?;
+        debug!(?path, ?source, "Extracted time from container metadata");
+        let (timestamp, offset) = time.into_local(path, config.zone_rules());
+        Some(ExtractedTime {
+            timestamp,
+            source: *source,
+            precision: TimePrecision::Second,
+            offset,
+            clock_correction: None,
+        })
+    })
+}
+
+/// Try video metadata via FFprobe
+fn extract_from_ffprobe(path: &Path, config: &Config) -> Option<ExtractedTime> {
+    let time = video::extract_video_time(path).ok()?;
+    debug!(?path, "Extracted time from video metadata");
+    let (timestamp, offset) = time.into_local(path, config.zone_rules());
+    Some(ExtractedTime {
+        timestamp,
+        source: TimeSource::VideoMetadata,
+        precision: TimePrecision::Second,
+        offset,
+        clock_correction: None,
+    })
+}
+
+/// Try Google Takeout JSON metadata
+fn extract_from_export(path: &Path, config: &Config) -> Option<ExtractedTime> {
+    let time = export::extract_export_time(path).ok()?;
+    debug!(?path, "Extracted time from export metadata");
+    let (timestamp, offset) = time.into_local(path, config.zone_rules());
+    Some(ExtractedTime {
+        timestamp,
+        source: TimeSource::ExportMetadata,
+        precision: TimePrecision::Second,
+        offset,
+        clock_correction: None,
+    })
+}
 
-    // Try filename parsing
-    if let Some(filename) = path.file_name().and_then(|f| f.to_str()) {
-        if let Some(time) = filename::parse_filename_time(filename) {
-            debug!(?path, "Extracted time from filename");
-            return Ok(ExtractedTime {
-                timestamp: time,
-                source: TimeSource::Filename,
-            });
+/// Try parsing the filename
+fn extract_from_filename(path: &Path, config: &Config) -> Option<ExtractedTime> {
+    let filename = path.file_name().and_then(|f| f.to_str())?;
+    let (time, precision) =
+        filename::parse_filename_timestamp(filename, &config.filename_patterns)?;
+    debug!(?path, ?precision, "Extracted time from filename");
+    let (timestamp, offset) = match time {
+        ContainerTime::Utc(utc) => {
+            let (local, offset) = config.zone_rules().to_local(utc);
+            (local, Some(offset))
         }
-        debug!(?path, "No time found in filename, using file system time");
-    }
+        other => (other.naive(), None),
+    };
+    Some(ExtractedTime {
+        timestamp,
+        source: TimeSource::Filename,
+        precision,
+        offset,
+        clock_correction: None,
+    })
+}
 
-    // Fall back to file system modification time
-    let metadata = fs::metadata(path)?;
-    let modified = metadata.modified()?;
-    let datetime: chrono::DateTime<chrono::Utc> = modified.into();
-    let naive = datetime.naive_utc();
+/// Try the names of the enclosing directories, up to the input directory
+fn extract_from_directory(path: &Path, config: &Config) -> Option<ExtractedTime> {
+    let root = config
+        .input_dirs
+        .iter()
+        .filter(|dir| path.starts_with(dir))
+        .max_by_key(|dir| dir.components().count());
+    let (timestamp, precision) = directory::parse_directory_date(path, root.map(PathBuf::as_path))?;
+    debug!(?path, ?precision, "Extracted time from directory name");
+    Some(ExtractedTime {
+        timestamp,
+        source: TimeSource::DirectoryName,
+        precision,
+        offset: None,
+        clock_correction: None,
+    })
+}
 
-    warn!(?path, "Using file system modification time as fallback");
+/// Use the file system time selected by [`Config::file_time`]
+///
+/// Birth time is not recorded by every file system; modification time is
+/// used instead when it is missing.
+fn extract_from_file_time(path: &Path, config: &Config) -> Option<ExtractedTime> {
+    let metadata = fs::metadata(path).ok()?;
+    let modified = metadata.modified().ok();
+    let created = metadata.created().ok();
 
-    Ok(ExtractedTime {
-        timestamp: naive,
-        source: TimeSource::FileSystem,
+    let (time, source) = match (config.file_time, created, modified) {
+        (FileTimeKind::Btime, Some(created), _) => (created, TimeSource::FileCreated),
+        (FileTimeKind::Oldest, Some(created), Some(modified)) if created < modified => {
+            (created, TimeSource::FileCreated)
+        }
+        (_, _, Some(modified)) => (modified, TimeSource::FileSystem),
+        (FileTimeKind::Oldest, Some(created), None) => (created, TimeSource::FileCreated),
+        _ => return None,
+    };
+    let datetime: chrono::DateTime<chrono::Utc> = time.into();
+    let (timestamp, offset) = config.zone_rules().to_local(datetime.naive_utc());
+
+    warn!(?path, ?source, "Using file system time as fallback");
+
+    Some(ExtractedTime {
+        timestamp,
+        source,
+        precision: TimePrecision::Second,
+        offset: Some(offset),
+        clock_correction: None,
     })
 }
 
+/// Resolve the local time and offset of an EXIF date
+///
+/// An explicit offset tag wins. Without one, the GPS UTC time is converted to
+/// the local time at the GPS coordinates, since the camera clock may still be
+/// set to another zone. With coordinates but no GPS time, only the offset is
+/// looked up and the camera's wall-clock time is kept. A GPS time without
+/// coordinates is only used when a timezone schedule window covers it.
+///
+/// GPS time stamps are whole seconds, so the sub-seconds of the camera time
+/// are kept when the GPS time is used.
+fn resolve_exif_time(time: &ExifTime, rules: ZoneRules) -> (NaiveDateTime, Option<FixedOffset>) {
+    if time.offset.is_some() {
+        return (time.datetime, time.offset);
+    }
+    let gps_time = time.gps_time.map(|utc| {
+        utc.with_nanosecond(time.datetime.nanosecond())
+            .unwrap_or(utc)
+    });
+    match (gps_time, time.location) {
+        (Some(utc), Some(location)) => {
+            let (local, offset) = timezone::utc_to_local(utc, location);
+            debug!(utc_time = %utc, local_time = %local, %offset, "Resolved EXIF time from GPS");
+            (local, Some(offset))
+        }
+        (None, Some(location)) => (
+            time.datetime,
+            Some(timezone::offset_at(location, time.datetime)),
+        ),
+        (Some(utc), None) => match rules.scheduled_zone(utc) {
+            Some(zone) => {
+                let (local, offset) = timezone::utc_to_zone(utc, Some(zone));
+                (local, Some(offset))
+            }
+            None => (time.datetime, None),
+        },
+        (None, None) => (time.datetime, None),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_extracted_time_utc() {
+        let time = ExtractedTime {
+            timestamp: datetime::parse_exif("2024:01:15 22:30:00").unwrap(),
+            source: TimeSource::Exif,
+            precision: TimePrecision::Second,
+            offset: FixedOffset::east_opt(8 * 3600),
+            clock_correction: None,
+        };
+        assert_eq!(time.utc(), datetime::parse_exif("2024:01:15 14:30:00"));
+
+        let time = ExtractedTime {
+            offset: None,
+            ..time
+        };
+        assert!(time.utc().is_none());
+    }
+
+    #[test]
+    fn test_latest_possible() {
+        let time = ExtractedTime {
+            timestamp: datetime::parse_exif("2024:01:15 00:00:00").unwrap(),
+            source: TimeSource::DirectoryName,
+            precision: TimePrecision::Day,
+            offset: None,
+            clock_correction: None,
+        };
+        assert_eq!(
+            time.latest_possible(),
+            datetime::parse_exif("2024:01:15 23:59:59")
+        );
+
+        let time = ExtractedTime {
+            precision: TimePrecision::Guessed,
+            ..time
+        };
+        assert!(time.latest_possible().is_none());
+    }
+
+    #[test]
+    fn test_resolve_exif_time() {
+        let berlin = timezone::GeoLocation::new(52.52, 13.405);
+        let time = ExifTime {
+            datetime: datetime::parse_exif("2024:07:15 09:00:00").unwrap(),
+            offset: None,
+            gps_time: datetime::parse_exif("2024:07:15 10:00:00"),
+            location: berlin,
+        };
+
+        // Camera clock left on another zone: GPS time wins
+        let (timestamp, offset) = resolve_exif_time(&time, ZoneRules::default());
+        assert_eq!(
+            timestamp,
+            datetime::parse_exif("2024:07:15 12:00:00").unwrap()
+        );
+        assert_eq!(offset.unwrap().local_minus_utc(), 2 * 3600);
+
+        // Sub-seconds of the camera time are carried over to the GPS time
+        let subsec = ExifTime {
+            datetime: time.datetime + Duration::milliseconds(250),
+            ..time
+        };
+        let (timestamp, _) = resolve_exif_time(&subsec, ZoneRules::default());
+        assert_eq!(
+            timestamp,
+            datetime::parse_exif("2024:07:15 12:00:00").unwrap() + Duration::milliseconds(250)
+        );
+
+        // Explicit offset tag wins over GPS
+        let tagged = ExifTime {
+            offset: FixedOffset::east_opt(3600),
+            ..time
+        };
+        assert_eq!(
+            resolve_exif_time(&tagged, ZoneRules::default()),
+            (tagged.datetime, tagged.offset)
+        );
+
+        // Location only: keep the camera time, look up the offset
+        let no_gps_time = ExifTime {
+            gps_time: None,
+            ..time
+        };
+        let (timestamp, offset) = resolve_exif_time(&no_gps_time, ZoneRules::default());
+        assert_eq!(timestamp, time.datetime);
+        assert_eq!(offset.unwrap().local_minus_utc(), 2 * 3600);
+
+        let no_location = ExifTime {
+            location: None,
+            ..time
+        };
+        assert_eq!(
+            resolve_exif_time(&no_location, ZoneRules::default()),
+            (time.datetime, None)
+        );
+
+        // GPS time without coordinates inside a scheduled trip
+        let schedule = [crate::config::TimezoneWindow {
+            from: chrono::NaiveDate::from_ymd_opt(2024, 7, 1).unwrap(),
+            to: chrono::NaiveDate::from_ymd_opt(2024, 7, 20).unwrap(),
+            tz: chrono_tz::Asia::Tokyo,
+        }];
+        let rules = ZoneRules {
+            home: None,
+            schedule: &schedule,
+        };
+        let (timestamp, offset) = resolve_exif_time(&no_location, rules);
+        assert_eq!(
+            timestamp,
+            datetime::parse_exif("2024:07:15 19:00:00").unwrap()
+        );
+        assert_eq!(offset.unwrap().local_minus_utc(), 9 * 3600);
+    }
+
+    #[test]
+    fn test_time_source_chain() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("IMG_20240115_143000.jpg");
+        fs::write(&path, b"not a jpeg").unwrap();
+
+        let mut config = Config::default();
+        let time = extract_time(&path, &config).unwrap();
+        assert_eq!(time.source, TimeSource::Filename);
+
+        config.time_sources = vec![TimeSourceKind::Mtime, TimeSourceKind::Filename];
+        let time = extract_time(&path, &config).unwrap();
+        assert_eq!(time.source, TimeSource::FileSystem);
+
+        config.time_sources = vec![TimeSourceKind::Exif];
+        assert!(extract_time(&path, &config).is_err());
+
+        // Directory overrides win over the global chain
+        config.time_sources_by_dir = vec![crate::config::DirTimeSources {
+            path: dir.path().to_path_buf(),
+            time_sources: vec![TimeSourceKind::Filename],
+        }];
+        let time = extract_time(&path, &config).unwrap();
+        assert_eq!(time.source, TimeSource::Filename);
+    }
+
+    /// Write a minimal JPEG whose EXIF block holds the given ASCII tags
+    fn write_exif_jpeg(path: &Path, tags: &[(::exif::Tag, &str)]) {
+        use ::exif::{Field, In, Value};
+
+        let fields: Vec<_> = tags
+            .iter()
+            .map(|(tag, value)| Field {
+                tag: *tag,
+                ifd_num: In::PRIMARY,
+                value: Value::Ascii(vec![value.as_bytes().to_vec()]),
+            })
+            .collect();
+        let mut writer = ::exif::experimental::Writer::new();
+        for field in &fields {
+            writer.push_field(field);
+        }
+        let mut tiff = std::io::Cursor::new(Vec::new());
+        writer.write(&mut tiff, false).unwrap();
+        let tiff = tiff.into_inner();
+
+        let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE1];
+        jpeg.extend_from_slice(&((tiff.len() + 8) as u16).to_be_bytes());
+        jpeg.extend_from_slice(b"Exif\0\0");
+        jpeg.extend_from_slice(&tiff);
+        jpeg.extend_from_slice(&[0xFF, 0xD9]);
+        fs::write(path, jpeg).unwrap();
+    }
+
+    #[test]
+    fn test_camera_clock_correction() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("photo.jpg");
+        write_exif_jpeg(
+            &path,
+            &[
+                (::exif::Tag::Make, "Canon"),
+                (::exif::Tag::Model, "Canon EOS R6"),
+                (::exif::Tag::DateTimeOriginal, "2023:05:01 13:12:00"),
+            ],
+        );
+
+        let mut config: Config = toml::from_str(
+            r#"
+            input_dirs = []
+            output_dir = "out"
+            processing_mode = "full"
+            classification = "none"
+            operation = "copy"
+            deduplicate = false
+            state_file = "state.json"
+            threads = 0
+            large_file_threshold = 0
+            dry_run = true
+            verbose = false
+            image_extensions = ["jpg"]
+            video_extensions = []
+            raw_extensions = []
+
+            [[camera_clock_offsets]]
+            make = "canon"
+            model = "Canon EOS R6"
+            from = 2022-01-01
+            to = "2023-12-31"
+            correction = "-01:12"
+            "#,
+        )
+        .unwrap();
+
+        let time = extract_time(&path, &config).unwrap();
+        assert_eq!(time.source, TimeSource::Exif);
+        assert_eq!(
+            time.timestamp,
+            datetime::parse_exif("2023:05:01 12:00:00").unwrap()
+        );
+        assert_eq!(time.clock_correction, Some(Duration::minutes(-72)));
+
+        // Outside the date range or for another body nothing changes
+        config.camera_clock_offsets[0].to = chrono::NaiveDate::from_ymd_opt(2022, 12, 31);
+        assert_eq!(extract_time(&path, &config).unwrap().clock_correction, None);
+        config.camera_clock_offsets[0].to = None;
+        config.camera_clock_offsets[0].serial = Some("123456".into());
+        assert_eq!(extract_time(&path, &config).unwrap().clock_correction, None);
+    }
+
+    #[test]
+    fn test_file_time_kind() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("photo.jpg");
+        fs::write(&path, b"not a jpeg").unwrap();
+        let has_btime = fs::metadata(&path).unwrap().created().is_ok();
+
+        let mut config = Config {
+            time_sources: vec![TimeSourceKind::Mtime],
+            ..Config::default()
+        };
+        let time = extract_time(&path, &config).unwrap();
+        assert_eq!(time.source, TimeSource::FileSystem);
+
+        config.file_time = FileTimeKind::Btime;
+        let time = extract_time(&path, &config).unwrap();
+        let expected = if has_btime {
+            TimeSource::FileCreated
+        } else {
+            TimeSource::FileSystem
+        };
+        assert_eq!(time.source, expected);
+
+        // An edit-proof birth time loses against an older modification time
+        let old = filetime::FileTime::from_unix_time(1_600_000_000, 0);
+        filetime::set_file_mtime(&path, old).unwrap();
+        config.file_time = FileTimeKind::Oldest;
+        let time = extract_time(&path, &config).unwrap();
+        assert_eq!(time.source, TimeSource::FileSystem);
+        assert_eq!(time.utc().unwrap().and_utc().timestamp(), 1_600_000_000);
+    }
+
+    #[test]
+    fn test_implausible_timestamp_falls_through() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            time_sources: vec![TimeSourceKind::Filename, TimeSourceKind::Mtime],
+            ..Config::default()
+        };
+
+        // Reset date of a camera with a dead clock battery
+        let path = dir.path().join("IMG_20000101_000000.jpg");
+        fs::write(&path, b"not a jpeg").unwrap();
+        let time = extract_time(&path, &config).unwrap();
+        assert_eq!(time.source, TimeSource::FileSystem);
+
+        // A real photo taken later on a sentinel date is kept
+        let path = dir.path().join("IMG_20000101_143200.jpg");
+        fs::write(&path, b"not a jpeg").unwrap();
+        let time = extract_time(&path, &config).unwrap();
+        assert_eq!(time.source, TimeSource::Filename);
+
+        // Far in the future
+        let path = dir.path().join("IMG_20990101_120000.jpg");
+        fs::write(&path, b"not a jpeg").unwrap();
+        let time = extract_time(&path, &config).unwrap();
+        assert_eq!(time.source, TimeSource::FileSystem);
+
+        let path = dir.path().join("IMG_20240115_143000.jpg");
+        fs::write(&path, b"not a jpeg").unwrap();
+        let time = extract_time(&path, &config).unwrap();
+        assert_eq!(time.source, TimeSource::Filename);
+
+        let config = Config {
+            time_sources: vec![TimeSourceKind::Filename],
+            plausibility: crate::config::PlausibilityWindow {
+                min_year: 2025,
+                ..Default::default()
+            },
+            ..Config::default()
+        };
+        assert!(extract_time(&path, &config).is_err());
+    }
+
     #[test]
     fn test_time_source_debug() {
         assert_eq!(format!("{:?}", TimeSource::Exif), "Exif");
+        assert_eq!(format!("{:?}", TimeSource::XmpSidecar), "XmpSidecar");
+        assert_eq!(format!("{:?}", TimeSource::IsoBmff), "IsoBmff");
+        assert_eq!(format!("{:?}", TimeSource::Riff), "Riff");
+        assert_eq!(format!("{:?}", TimeSource::Matroska), "Matroska");
+        assert_eq!(format!("{:?}", TimeSource::Avchd), "Avchd");
         assert_eq!(format!("{:?}", TimeSource::VideoMetadata), "VideoMetadata");
+        assert_eq!(
+            format!("{:?}", TimeSource::ExportMetadata),
+            "ExportMetadata"
+        );
         assert_eq!(format!("{:?}", TimeSource::Filename), "Filename");
+        assert_eq!(format!("{:?}", TimeSource::DirectoryName), "DirectoryName");
         assert_eq!(format!("{:?}", TimeSource::FileSystem), "FileSystem");
+        assert_eq!(format!("{:?}", TimeSource::FileCreated), "FileCreated");
+        assert_eq!(format!("{:?}", TimeSource::Interpolated), "Interpolated");
     }
 }
@@ -0,0 +1,225 @@
+//! Native RIFF/AVI creation time extraction
+//!
+//! Older digital cameras record the shooting time in the AVI header list:
+//! - `IDIT` chunk: a ctime-style (`MON JAN 15 14:30:00 2024`) or EXIF-style
+//!   date string
+//! - `strd` chunk: vendor stream data that often embeds an EXIF date string
+//!
+//! Both values are local wall-clock times.
+
+use crate::error::{Error, Result};
+use crate::time::datetime::parse_exif;
+use crate::time::video::ContainerTime;
+use chrono::NaiveDateTime;
+use regex::bytes::Regex;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::OnceLock;
+use tracing::trace;
+
+/// Upper bound for the size of a header list we are willing to load into memory
+const MAX_LIST_SIZE: u32 = 16 * 1024 * 1024;
+
+/// Formats used by the `IDIT` chunk
+const IDIT_FORMATS: &[&str] = &["%a %b %d %H:%M:%S %Y", "%a %b %e %H:%M:%S %Y"];
+
+/// Pattern: EXIF-style date string embedded in binary data
+static PATTERN_EXIF_DATE: OnceLock<Regex> = OnceLock::new();
+
+/// Get the embedded EXIF date pattern
+fn get_pattern_exif_date() -> &'static Regex {
+    PATTERN_EXIF_DATE.get_or_init(|| Regex::new(r"\d{4}:\d{2}:\d{2} \d{2}:\d{2}:\d{2}").unwrap())
+}
+
+/// Extract creation time from a RIFF/AVI container
+pub fn extract_riff_time(path: &Path) -> Result<ContainerTime> {
+    let file = File::open(path)?;
+    let mut reader = BufReader::new(file);
+
+    let make_error = |message: &str| Error::VideoMetadata {
+        path: path.to_path_buf(),
+        message: message.to_string(),
+    };
+
+    let mut header = [0u8; 12];
+    reader
+        .read_exact(&mut header)
+        .map_err(|_| make_error("Not a RIFF container"))?;
+    if &header[0..4] != b"RIFF" || &header[8..12] != b"AVI " {
+        return Err(make_error("Not a RIFF/AVI container"));
+    }
+
+    let hdrl = read_header_list(&mut reader).ok_or_else(|| make_error("No hdrl list found"))?;
+
+    let mut strd_time = None;
+    if let Some(time) = scan_chunks(&hdrl, &mut strd_time) {
+        trace!(?path, "Found IDIT chunk");
+        return Ok(ContainerTime::Local(time));
+    }
+    if let Some(time) = strd_time {
+        trace!(?path, "Found date in strd chunk");
+        return Ok(ContainerTime::Local(time));
+    }
+
+    Err(make_error("No creation time found in RIFF chunks"))
+}
+
+/// Find the top-level `LIST hdrl` and return its contents (after the list type)
+fn read_header_list<R: Read + Seek>(reader: &mut R) -> Option<Vec<u8>> {
+    loop {
+        let mut chunk_header = [0u8; 8];
+        reader.read_exact(&mut chunk_header).ok()?;
+        let size = u32::from_le_bytes(chunk_header[4..8].try_into().ok()?);
+
+        if &chunk_header[0..4] == b"LIST" && size >= 4 {
+            let mut list_type = [0u8; 4];
+            reader.read_exact(&mut list_type).ok()?;
+            if &list_type == b"hdrl" {
+                if size > MAX_LIST_SIZE {
+                    return None;
+                }
+                let mut data = vec![0u8; (size - 4) as usize];
+                reader.read_exact(&mut data).ok()?;
+                return Some(data);
+            }
+            // The hdrl list always precedes movi, no need to go further
+            if &list_type == b"movi" {
+                return None;
+            }
+            reader
+                .seek(SeekFrom::Current(padded(size) as i64 - 4))
+                .ok()?;
+        } else {
+            reader.seek(SeekFrom::Current(padded(size) as i64)).ok()?;
+        }
+    }
+}
+
+/// Walk chunks in memory, returning the `IDIT` time and remembering the first `strd` time
+fn scan_chunks(data: &[u8], strd_time: &mut Option<NaiveDateTime>) -> Option<NaiveDateTime> {
+    let mut pos = 0usize;
+    while pos + 8 <= data.len() {
+        let id = &data[pos..pos + 4];
+        let size = u32::from_le_bytes(data[pos + 4..pos + 8].try_into().ok()?) as usize;
+        let end = (pos + 8 + size).min(data.len());
+        let payload = &data[pos + 8..end];
+
+        match id {
+            b"LIST" if payload.len() >= 4 => {
+                if let Some(time) = scan_chunks(&payload[4..], strd_time) {
+                    return Some(time);
+                }
+            }
+            b"IDIT" => {
+                if let Some(time) = parse_idit(payload) {
+                    return Some(time);
+                }
+            }
+            b"strd" if strd_time.is_none() => {
+                *strd_time = find_embedded_exif_date(payload);
+            }
+            _ => {}
+        }
+
+        pos += 8 + padded(size as u32) as usize;
+    }
+    None
+}
+
+/// Parse the value of an `IDIT` chunk
+fn parse_idit(payload: &[u8]) -> Option<NaiveDateTime> {
+    let text = String::from_utf8_lossy(payload);
+    let text = text.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+
+    // ctime-style, e.g. "MON JAN 15 14:30:00 2024"
+    for format in IDIT_FORMATS {
+        if let Ok(dt) = NaiveDateTime::parse_from_str(text, format) {
+            return Some(dt);
+        }
+    }
+
+    parse_exif(text)
+}
+
+/// Find an EXIF-style date string embedded in binary chunk data
+fn find_embedded_exif_date(payload: &[u8]) -> Option<NaiveDateTime> {
+    let found = get_pattern_exif_date().find(payload)?;
+    parse_exif(std::str::from_utf8(found.as_bytes()).ok()?)
+}
+
+/// RIFF chunks are padded to an even number of bytes
+fn padded(size: u32) -> u32 {
+    size + (size & 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Datelike, Timelike};
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn make_chunk(id: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut out = id.to_vec();
+        out.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        out.extend_from_slice(payload);
+        if payload.len() % 2 == 1 {
+            out.push(0);
+        }
+        out
+    }
+
+    fn make_list(list_type: &[u8; 4], children: &[u8]) -> Vec<u8> {
+        let mut payload = list_type.to_vec();
+        payload.extend_from_slice(children);
+        make_chunk(b"LIST", &payload)
+    }
+
+    fn write_avi(hdrl_children: &[u8]) -> NamedTempFile {
+        let mut body = b"AVI ".to_vec();
+        body.extend(make_list(b"hdrl", hdrl_children));
+        body.extend(make_list(b"movi", &[0u8; 16]));
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(&make_chunk(b"RIFF", &body)).unwrap();
+        file.flush().unwrap();
+        file
+    }
+
+    #[test]
+    fn test_idit_ctime_format() {
+        let mut children = make_chunk(b"avih", &[0u8; 56]);
+        children.extend(make_chunk(b"IDIT", b"MON JAN 15 14:30:00 2024\n\0"));
+        let file = write_avi(&children);
+
+        let ContainerTime::Local(dt) = extract_riff_time(file.path()).unwrap() else {
+            panic!("expected local time");
+        };
+        assert_eq!(dt.year(), 2024);
+        assert_eq!(dt.month(), 1);
+        assert_eq!(dt.day(), 15);
+        assert_eq!(dt.hour(), 14);
+    }
+
+    #[test]
+    fn test_strd_embedded_date() {
+        let mut strd = b"AVIF\0\0II*\0".to_vec();
+        strd.extend_from_slice(b"2019:08:03 10:11:12\0");
+        let strl = make_list(b"strl", &make_chunk(b"strd", &strd));
+        let file = write_avi(&strl);
+
+        let ContainerTime::Local(dt) = extract_riff_time(file.path()).unwrap() else {
+            panic!("expected local time");
+        };
+        assert_eq!(dt.year(), 2019);
+        assert_eq!(dt.second(), 12);
+    }
+
+    #[test]
+    fn test_not_riff() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(b"\0\0\0\x18ftypmp42").unwrap();
+        file.flush().unwrap();
+        assert!(extract_riff_time(file.path()).is_err());
+    }
+}
@@ -0,0 +1,198 @@
+//! Timestamps for undated files from camera sequence numbers
+//!
+//! Cameras number their files consecutively (`DSC_0042.JPG`, `DSC_0043.JPG`,
+//! ...), so a file that only has a file system time can be placed between the
+//! nearest files of the same folder and sequence that carry a real timestamp.
+//! The time is interpolated linearly by sequence number. Files outside the
+//! dated range of their sequence are left alone, as are sequences whose
+//! numbers run backwards in time (counter resets, several cameras).
+
+use super::{ExtractedTime, TimePrecision, TimeSource};
+use chrono::{Duration, NaiveDateTime};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use tracing::{debug, trace};
+
+/// Minimum number of digits in a sequence number
+const MIN_SEQUENCE_DIGITS: usize = 3;
+
+/// Folder and filename prefix shared by the files of one sequence
+type SequenceKey = (PathBuf, String);
+
+/// Files of one sequence
+#[derive(Default)]
+struct Sequence<'a> {
+    /// Timestamps of dated files by sequence number
+    dated: BTreeMap<u64, NaiveDateTime>,
+    /// Files with only a file system time, or none at all
+    undated: Vec<(&'a Path, u64)>,
+}
+
+/// Split a filename into its lowercase prefix and sequence number
+///
+/// `DSC_0043.JPG` gives `("dsc_", 43)`.
+fn sequence_number(path: &Path) -> Option<(String, u64)> {
+    let stem = path.file_stem()?.to_str()?;
+    let prefix = stem.trim_end_matches(|c: char| c.is_ascii_digit());
+    let digits = &stem[prefix.len()..];
+    if digits.len() < MIN_SEQUENCE_DIGITS {
+        return None;
+    }
+    Some((prefix.to_lowercase(), digits.parse().ok()?))
+}
+
+/// Interpolate timestamps for files without a timestamp of their own
+///
+/// `times` holds the extraction result of every file; results from the file
+/// system count as undated. Returns the interpolated times by path.
+pub fn interpolate(times: &[(PathBuf, Option<ExtractedTime>)]) -> HashMap<PathBuf, ExtractedTime> {
+    let mut sequences: HashMap<SequenceKey, Sequence> = HashMap::new();
+
+    for (path, time) in times {
+        let Some((prefix, number)) = sequence_number(path) else {
+            continue;
+        };
+        let folder = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        let sequence = sequences.entry((folder, prefix)).or_default();
+        match time {
+            Some(time) if !time.source.is_file_system() => {
+                sequence.dated.entry(number).or_insert(time.timestamp);
+            }
+            _ => sequence.undated.push((path, number)),
+        }
+    }
+
+    let mut interpolated = HashMap::new();
+    for sequence in sequences.values() {
+        for &(path, number) in &sequence.undated {
+            let Some(timestamp) = interpolate_number(&sequence.dated, number) else {
+                debug!(?path, number, "No dated neighbours to interpolate from");
+                continue;
+            };
+            trace!(?path, number, %timestamp, "Interpolated time from sequence");
+            interpolated.insert(
+                path.to_path_buf(),
+                ExtractedTime {
+                    timestamp,
+                    source: TimeSource::Interpolated,
+                    precision: TimePrecision::Guessed,
+                    offset: None,
+                    clock_correction: None,
+                },
+            );
+        }
+    }
+    interpolated
+}
+
+/// Time of `number` between the nearest dated numbers on either side
+fn interpolate_number(dated: &BTreeMap<u64, NaiveDateTime>, number: u64) -> Option<NaiveDateTime> {
+    let (&before, &start) = dated.range(..=number).next_back()?;
+    let (&after, &end) = dated.range(number..).next()?;
+    if before == after {
+        return Some(start);
+    }
+    if end < start {
+        return None;
+    }
+
+    let span = (end - start).num_seconds() as i128;
+    let offset = span * (number - before) as i128 / (after - before) as i128;
+    Some(start + Duration::seconds(offset as i64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(source: TimeSource, s: &str) -> Option<ExtractedTime> {
+        Some(ExtractedTime {
+            timestamp: NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap(),
+            source,
+            precision: TimePrecision::Second,
+            offset: None,
+            clock_correction: None,
+        })
+    }
+
+    fn interpolated_time(result: &HashMap<PathBuf, ExtractedTime>, path: &str) -> Option<String> {
+        result
+            .get(Path::new(path))
+            .map(|t| t.timestamp.format("%Y-%m-%d %H:%M:%S").to_string())
+    }
+
+    #[test]
+    fn test_sequence_number() {
+        assert_eq!(
+            sequence_number(Path::new("a/DSC_0043.JPG")),
+            Some(("dsc_".into(), 43))
+        );
+        assert_eq!(
+            sequence_number(Path::new("P1010001.jpg")),
+            Some(("p".into(), 1010001))
+        );
+        assert_eq!(sequence_number(Path::new("IMG_1.jpg")), None);
+        assert_eq!(sequence_number(Path::new("holiday.jpg")), None);
+    }
+
+    #[test]
+    fn test_interpolate_between_dated_neighbours() {
+        let times = vec![
+            (
+                PathBuf::from("trip/DSC_0040.JPG"),
+                time(TimeSource::Exif, "2011-07-24 10:00:00"),
+            ),
+            (
+                PathBuf::from("trip/DSC_0041.JPG"),
+                time(TimeSource::FileSystem, "2019-03-01 08:00:00"),
+            ),
+            (PathBuf::from("trip/DSC_0043.JPG"), None),
+            (
+                PathBuf::from("trip/DSC_0044.JPG"),
+                time(TimeSource::Exif, "2011-07-24 14:00:00"),
+            ),
+            (
+                PathBuf::from("trip/DSC_0045.JPG"),
+                time(TimeSource::FileSystem, "2019-03-01 08:00:00"),
+            ),
+            (
+                PathBuf::from("other/DSC_0042.JPG"),
+                time(TimeSource::FileSystem, "2019-03-01 08:00:00"),
+            ),
+        ];
+        let result = interpolate(&times);
+
+        assert_eq!(
+            interpolated_time(&result, "trip/DSC_0041.JPG").as_deref(),
+            Some("2011-07-24 11:00:00")
+        );
+        assert_eq!(
+            interpolated_time(&result, "trip/DSC_0043.JPG").as_deref(),
+            Some("2011-07-24 13:00:00")
+        );
+        assert_eq!(
+            result[Path::new("trip/DSC_0043.JPG")].source,
+            TimeSource::Interpolated
+        );
+        // Past the last dated file, or in another folder
+        assert_eq!(interpolated_time(&result, "trip/DSC_0045.JPG"), None);
+        assert_eq!(interpolated_time(&result, "other/DSC_0042.JPG"), None);
+        assert_eq!(result.len(), 2);
+    }
+
+    #[test]
+    fn test_backwards_sequence_is_skipped() {
+        let times = vec![
+            (
+                PathBuf::from("DSC_9998.JPG"),
+                time(TimeSource::Exif, "2012-01-01 10:00:00"),
+            ),
+            (PathBuf::from("DSC_9999.JPG"), None),
+            (
+                PathBuf::from("DSC_10000.JPG"),
+                time(TimeSource::Exif, "2011-01-01 10:00:00"),
+            ),
+        ];
+        assert!(interpolate(&times).is_empty());
+    }
+}
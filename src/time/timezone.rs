@@ -0,0 +1,276 @@
+//! Offline timezone resolution from capture coordinates
+//!
+//! GPS timestamps and most video container dates are stored in UTC. When the
+//! file also records where it was shot, the zone is looked up in the zone
+//! boundary polygons embedded by `tzf-rs` and the UTC instant is converted
+//! with that zone's rules, so DST is applied for the date of the shot rather
+//! than for today. Points at sea resolve to nautical zones (`Etc/GMT+n`);
+//! zones unknown to `chrono-tz` fall back to nautical time (longitude / 15).
+//!
+//! UTC timestamps without a location are converted with [`ZoneRules`]: a
+//! matching date-range override from the schedule, else the configured home
+//! zone, else the system zone, always using the rules in effect at that
+//! instant.
+
+use crate::config::TimezoneWindow;
+use chrono::{FixedOffset, Local, NaiveDateTime, Offset, TimeZone};
+use chrono_tz::Tz;
+use std::sync::OnceLock;
+use tracing::{trace, warn};
+use tzf_rs::DefaultFinder;
+
+/// Embedded zone boundary polygons, loaded on first use
+static FINDER: OnceLock<DefaultFinder> = OnceLock::new();
+
+/// Geographic coordinates in decimal degrees
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoLocation {
+    /// Latitude, positive north of the equator
+    pub latitude: f64,
+    /// Longitude, positive east of Greenwich
+    pub longitude: f64,
+}
+
+impl GeoLocation {
+    /// Create a location, rejecting out-of-range coordinates
+    pub fn new(latitude: f64, longitude: f64) -> Option<Self> {
+        if !latitude.is_finite()
+            || !longitude.is_finite()
+            || latitude.abs() > 90.0
+            || longitude.abs() > 180.0
+        {
+            return None;
+        }
+        Some(Self {
+            latitude,
+            longitude,
+        })
+    }
+}
+
+/// Look up the IANA zone for a location
+pub fn zone_at(location: GeoLocation) -> Option<Tz> {
+    let finder = FINDER.get_or_init(DefaultFinder::new);
+    let name = finder.get_tz_name(location.longitude, location.latitude);
+    if name.is_empty() {
+        return None;
+    }
+    let zone = name.parse().ok();
+    if zone.is_none() {
+        warn!(name, "Unknown timezone in boundary data");
+    }
+    zone
+}
+
+/// UTC offset in effect at `location` for the given UTC instant
+pub fn offset_at(location: GeoLocation, utc: NaiveDateTime) -> FixedOffset {
+    match zone_at(location) {
+        Some(zone) => {
+            let offset = zone.offset_from_utc_datetime(&utc).fix();
+            trace!(?location, %zone, %offset, "Resolved timezone from location");
+            offset
+        }
+        None => {
+            let hours = (location.longitude / 15.0).round() as i32;
+            trace!(
+                ?location,
+                hours, "No timezone region found, using nautical time"
+            );
+            FixedOffset::east_opt(hours * 3600).unwrap()
+        }
+    }
+}
+
+/// Convert a UTC timestamp to the local wall-clock time at `location`
+pub fn utc_to_local(utc: NaiveDateTime, location: GeoLocation) -> (NaiveDateTime, FixedOffset) {
+    let offset = offset_at(location, utc);
+    let local = utc + chrono::Duration::seconds(offset.local_minus_utc() as i64);
+    (local, offset)
+}
+
+/// Timezone rules for UTC timestamps that have no capture location
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ZoneRules<'a> {
+    /// Home timezone (None = system timezone)
+    pub home: Option<Tz>,
+    /// Date-range overrides, checked in order
+    pub schedule: &'a [TimezoneWindow],
+}
+
+impl ZoneRules<'_> {
+    /// Zone of the first schedule window containing `utc`
+    ///
+    /// A window matches when the local date of `utc` in the window's own
+    /// zone lies within its range.
+    pub fn scheduled_zone(&self, utc: NaiveDateTime) -> Option<Tz> {
+        self.schedule
+            .iter()
+            .find(|window| {
+                let date = window.tz.from_utc_datetime(&utc).date_naive();
+                (window.from..=window.to).contains(&date)
+            })
+            .map(|window| window.tz)
+    }
+
+    /// Convert a UTC timestamp to local time
+    pub fn to_local(&self, utc: NaiveDateTime) -> (NaiveDateTime, FixedOffset) {
+        match self.scheduled_zone(utc) {
+            Some(zone) => {
+                trace!(%utc, %zone, "Using scheduled timezone");
+                utc_to_zone(utc, Some(zone))
+            }
+            None => utc_to_zone(utc, self.home),
+        }
+    }
+}
+
+/// Convert a UTC timestamp with the configured zone, or the system zone
+pub fn utc_to_zone(utc: NaiveDateTime, zone: Option<Tz>) -> (NaiveDateTime, FixedOffset) {
+    let offset = match zone {
+        Some(zone) => zone.offset_from_utc_datetime(&utc).fix(),
+        None => Local.offset_from_utc_datetime(&utc).fix(),
+    };
+    let local = utc + chrono::Duration::seconds(offset.local_minus_utc() as i64);
+    (local, offset)
+}
+
+/// Parse an ISO 6709 location string such as `+48.8584+002.2945+035.000/`
+///
+/// Used by QuickTime (`©xyz`, `com.apple.quicktime.location.ISO6709`) and
+/// FFprobe's `location` tag. Only the decimal degrees form is supported.
+pub fn parse_iso6709(s: &str) -> Option<GeoLocation> {
+    let s = s.trim().trim_end_matches('/');
+    let mut parts = Vec::with_capacity(3);
+    let mut start = None;
+    for (i, c) in s.char_indices() {
+        if c == '+' || c == '-' {
+            if let Some(start) = start {
+                parts.push(&s[start..i]);
+            }
+            start = Some(i);
+        } else if !(c.is_ascii_digit() || c == '.') {
+            return None;
+        }
+    }
+    parts.push(&s[start?..]);
+
+    let latitude = parts.first()?.parse().ok()?;
+    let longitude = parts.get(1)?.parse().ok()?;
+    GeoLocation::new(latitude, longitude)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Timelike;
+
+    fn utc(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn test_zone_at() {
+        let paris = GeoLocation::new(48.8584, 2.2945).unwrap();
+        assert_eq!(zone_at(paris), Some(chrono_tz::Europe::Paris));
+
+        let hong_kong = GeoLocation::new(22.3, 114.17).unwrap();
+        assert_eq!(zone_at(hong_kong), Some(chrono_tz::Asia::Hong_Kong));
+
+        let new_york = GeoLocation::new(40.7128, -74.006).unwrap();
+        assert_eq!(zone_at(new_york), Some(chrono_tz::America::New_York));
+
+        let mid_pacific = GeoLocation::new(0.0, -140.0).unwrap();
+        assert_eq!(zone_at(mid_pacific), Some(chrono_tz::Etc::GMTPlus9));
+    }
+
+    #[test]
+    fn test_zone_at_border_cities() {
+        let zone = |latitude, longitude| zone_at(GeoLocation::new(latitude, longitude).unwrap());
+        assert_eq!(zone(16.87, 96.2), Some(chrono_tz::Asia::Yangon));
+        assert_eq!(zone(29.65, 91.1), Some(chrono_tz::Asia::Shanghai));
+        assert_eq!(zone(28.6, 77.2), Some(chrono_tz::Asia::Kolkata));
+        assert_eq!(zone(29.42, -98.49), Some(chrono_tz::America::Chicago));
+        assert_eq!(zone(27.5, -99.5), Some(chrono_tz::America::Chicago));
+        assert_eq!(zone(25.67, -100.31), Some(chrono_tz::America::Monterrey));
+        assert_eq!(zone(35.05, -85.31), Some(chrono_tz::America::New_York));
+        assert_eq!(zone(38.88, -6.97), Some(chrono_tz::Europe::Madrid));
+        assert_eq!(zone(37.26, -6.94), Some(chrono_tz::Europe::Madrid));
+        assert_eq!(zone(38.72, -9.14), Some(chrono_tz::Europe::Lisbon));
+    }
+
+    #[test]
+    fn test_utc_to_local_applies_dst_of_the_shot() {
+        let berlin = GeoLocation::new(52.52, 13.405).unwrap();
+
+        let (local, offset) = utc_to_local(utc("2024-07-15 10:00:00"), berlin);
+        assert_eq!(local.hour(), 12);
+        assert_eq!(offset.local_minus_utc(), 2 * 3600);
+
+        let (local, offset) = utc_to_local(utc("2024-01-15 10:00:00"), berlin);
+        assert_eq!(local.hour(), 11);
+        assert_eq!(offset.local_minus_utc(), 3600);
+    }
+
+    #[test]
+    fn test_utc_to_zone_uses_rules_of_the_instant() {
+        let zone = Some(chrono_tz::Europe::Berlin);
+
+        let (local, offset) = utc_to_zone(utc("2024-07-31 22:30:00"), zone);
+        assert_eq!(local, utc("2024-08-01 00:30:00"));
+        assert_eq!(offset.local_minus_utc(), 2 * 3600);
+
+        let (local, offset) = utc_to_zone(utc("2024-12-31 22:30:00"), zone);
+        assert_eq!(local, utc("2024-12-31 23:30:00"));
+        assert_eq!(offset.local_minus_utc(), 3600);
+    }
+
+    #[test]
+    fn test_zone_rules_schedule() {
+        let schedule = [TimezoneWindow {
+            from: chrono::NaiveDate::from_ymd_opt(2024, 7, 1).unwrap(),
+            to: chrono::NaiveDate::from_ymd_opt(2024, 7, 20).unwrap(),
+            tz: chrono_tz::Asia::Tokyo,
+        }];
+        let rules = ZoneRules {
+            home: Some(chrono_tz::Europe::Berlin),
+            schedule: &schedule,
+        };
+
+        // 2024-07-20 16:00 UTC is already July 21st in Tokyo
+        let (local, _) = rules.to_local(utc("2024-07-20 14:00:00"));
+        assert_eq!(local, utc("2024-07-20 23:00:00"));
+        let (local, _) = rules.to_local(utc("2024-07-20 16:00:00"));
+        assert_eq!(local, utc("2024-07-20 18:00:00"));
+
+        // 2024-06-30 16:00 UTC is July 1st in Tokyo
+        let (local, offset) = rules.to_local(utc("2024-06-30 16:00:00"));
+        assert_eq!(local, utc("2024-07-01 01:00:00"));
+        assert_eq!(offset.local_minus_utc(), 9 * 3600);
+    }
+
+    #[test]
+    fn test_nautical_fallback() {
+        let mid_pacific = GeoLocation::new(0.0, -140.0).unwrap();
+        let (local, offset) = utc_to_local(utc("2024-01-15 10:00:00"), mid_pacific);
+        assert_eq!(offset.local_minus_utc(), -9 * 3600);
+        assert_eq!(local.hour(), 1);
+    }
+
+    #[test]
+    fn test_parse_iso6709() {
+        let location = parse_iso6709("+48.8584+002.2945+035.000/").unwrap();
+        assert!((location.latitude - 48.8584).abs() < 1e-9);
+        assert!((location.longitude - 2.2945).abs() < 1e-9);
+
+        let location = parse_iso6709("-33.8688+151.2093/").unwrap();
+        assert!(location.latitude < 0.0);
+
+        let location = parse_iso6709("+40.7128-074.0060/").unwrap();
+        assert!((location.longitude + 74.006).abs() < 1e-9);
+
+        assert!(parse_iso6709("").is_none());
+        assert!(parse_iso6709("+48.8584").is_none());
+        assert!(parse_iso6709("+95.0+002.0/").is_none());
+        assert!(parse_iso6709("Paris").is_none());
+    }
+}
@@ -1,9 +1,12 @@
 //! Video metadata extraction via FFprobe
+//!
+//! FFprobe is the fallback for containers the native parsers can't handle.
 
 use crate::error::{Error, Result};
 use crate::time::datetime::parse_video_datetime;
 use crate::time::filename::parse_filename_time;
-use chrono::{Local, NaiveDateTime};
+use crate::time::timezone::{self, GeoLocation, ZoneRules, parse_iso6709};
+use chrono::{FixedOffset, NaiveDateTime};
 use std::path::Path;
 use std::process::Command;
 use std::sync::OnceLock;
@@ -17,22 +20,66 @@ const CREATION_DATE_KEYS: &[&str] = &[
     "date_recorded",
 ];
 
+/// Metadata keys to try for the ISO 6709 recording location
+const LOCATION_KEYS: &[&str] = &[
+    "location",
+    "com.apple.quicktime.location.ISO6709",
+    "location-eng",
+];
+
+/// Timestamp read by one of the native container parsers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ContainerTime {
+    /// Local wall-clock time at the recording location
+    Local(NaiveDateTime),
+    /// UTC time that still needs timezone correction
+    Utc(NaiveDateTime),
+    /// UTC time recorded together with the capture location
+    Located(NaiveDateTime, GeoLocation),
+}
+
+impl ContainerTime {
+    /// The stored timestamp, without any timezone correction
+    pub fn naive(self) -> NaiveDateTime {
+        match self {
+            ContainerTime::Local(dt) | ContainerTime::Utc(dt) | ContainerTime::Located(dt, _) => dt,
+        }
+    }
+
+    /// Convert to local time, applying timezone correction to UTC values
+    ///
+    /// `rules` convert UTC values without a location. Returns the UTC offset
+    /// alongside the local time when it is known.
+    pub fn into_local(self, path: &Path, rules: ZoneRules) -> (NaiveDateTime, Option<FixedOffset>) {
+        match self {
+            ContainerTime::Local(dt) => (dt, None),
+            ContainerTime::Utc(dt) => {
+                let (local, offset) = apply_timezone_correction(path, dt, rules);
+                (local, Some(offset))
+            }
+            ContainerTime::Located(dt, location) => {
+                let (local, offset) = timezone::utc_to_local(dt, location);
+                debug!(?path, utc_time = %dt, local_time = %local, %offset, "Applied location timezone");
+                (local, Some(offset))
+            }
+        }
+    }
+}
+
 /// Cached FFprobe availability check
 static FFPROBE_AVAILABLE: OnceLock<bool> = OnceLock::new();
 
 /// Check if ffprobe is available (cached)
-fn is_ffprobe_available() -> bool {
+pub fn is_ffprobe_available() -> bool {
     *FFPROBE_AVAILABLE.get_or_init(|| Command::new("ffprobe").arg("-version").output().is_ok())
 }
 
 /// Extract creation time from video metadata using FFprobe
 ///
-/// Video metadata typically stores creation time in UTC. This function:
-/// 1. Extracts the UTC timestamp from video metadata
-/// 2. Attempts to parse the filename for a local timestamp
-/// 3. If filename has a valid timestamp, calculates timezone offset and applies correction
-/// 4. If no valid filename timestamp, converts UTC to local system timezone
-pub fn extract_video_time(path: &Path) -> Result<NaiveDateTime> {
+/// Video metadata typically stores creation time in UTC. The timestamp is
+/// returned together with the recording location when one is tagged, so the
+/// caller can convert it with [`ContainerTime::into_local`].
+pub fn extract_video_time(path: &Path) -> Result<ContainerTime> {
     // Check if ffprobe is available (cached)
     if !is_ffprobe_available() {
         return Err(Error::FfprobeNotFound);
@@ -124,9 +171,32 @@ pub fn extract_video_time(path: &Path) -> Result<NaiveDateTime> {
         message: "No creation time found in video metadata".to_string(),
     })?;
 
-    // Apply timezone correction
-    let corrected_time = apply_timezone_correction(path, utc_time);
-    Ok(corrected_time)
+    match find_location(&json) {
+        Some(location) => {
+            debug!(?path, ?location, "Found video recording location");
+            Ok(ContainerTime::Located(utc_time, location))
+        }
+        None => Ok(ContainerTime::Utc(utc_time)),
+    }
+}
+
+/// Find an ISO 6709 recording location in the format or stream tags
+fn find_location(json: &serde_json::Value) -> Option<GeoLocation> {
+    let format_tags = json.get("format").and_then(|f| f.get("tags"));
+    let stream_tags = json
+        .get("streams")
+        .and_then(|s| s.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|stream| stream.get("tags"));
+
+    format_tags.into_iter().chain(stream_tags).find_map(|tags| {
+        LOCATION_KEYS.iter().find_map(|key| {
+            tags.get(*key)
+                .and_then(|v| v.as_str())
+                .and_then(parse_iso6709)
+        })
+    })
 }
 
 /// Apply timezone correction to UTC video timestamp
@@ -134,8 +204,13 @@ pub fn extract_video_time(path: &Path) -> Result<NaiveDateTime> {
 /// Strategy:
 /// 1. Try to parse filename for a local timestamp
 /// 2. If filename has valid timestamp, calculate timezone offset from difference
-/// 3. If no valid filename timestamp, use local system timezone
-fn apply_timezone_correction(path: &Path, utc_time: NaiveDateTime) -> NaiveDateTime {
+/// 3. If no valid filename timestamp, use the scheduled, configured or system
+///    timezone, with the DST rules in effect at `utc_time`
+fn apply_timezone_correction(
+    path: &Path,
+    utc_time: NaiveDateTime,
+    rules: ZoneRules,
+) -> (NaiveDateTime, FixedOffset) {
     // Try to get filename timestamp
     if let Some(filename) = path.file_name().and_then(|f| f.to_str())
         && let Some(filename_time) = parse_filename_time(filename)
@@ -161,7 +236,8 @@ fn apply_timezone_correction(path: &Path, utc_time: NaiveDateTime) -> NaiveDateT
                 );
 
                 // Apply the offset to UTC time
-                return utc_time + chrono::Duration::seconds(offset_seconds);
+                let offset = FixedOffset::east_opt(offset_seconds as i32).unwrap();
+                return (utc_time + chrono::Duration::seconds(offset_seconds), offset);
             }
         } else {
             // Difference is too large, might be wrong filename or different date
@@ -170,25 +246,23 @@ fn apply_timezone_correction(path: &Path, utc_time: NaiveDateTime) -> NaiveDateT
                 utc_time = %utc_time,
                 filename_time = %filename_time,
                 diff_hours = diff_seconds / 3600,
-                "Filename timestamp differs too much from metadata, using system timezone"
+                "Filename timestamp differs too much from metadata, using fallback timezone"
             );
         }
     }
 
-    // Fall back to system local timezone
-    // Convert UTC naive datetime to local timezone
-    let local_offset = Local::now().offset().local_minus_utc();
-    let local_time = utc_time + chrono::Duration::seconds(local_offset as i64);
+    // Fall back to the scheduled, configured or system timezone
+    let (local_time, offset) = rules.to_local(utc_time);
 
     debug!(
         ?path,
         utc_time = %utc_time,
         local_time = %local_time,
-        offset_hours = local_offset / 3600,
-        "Applied system timezone offset"
+        %offset,
+        "Applied fallback timezone offset"
     );
 
-    local_time
+    (local_time, offset)
 }
 
 #[cfg(test)]
@@ -226,4 +300,45 @@ mod tests {
         // Invalid format
         assert!(parse_video_datetime("invalid").is_none());
     }
+
+    #[test]
+    fn test_find_location() {
+        let json: serde_json::Value = serde_json::from_str(
+            r#"{"format":{"tags":{"creation_time":"2024-07-15T10:00:00Z"}},
+                "streams":[{"tags":{}},{"tags":{"location":"+52.5200+013.4050/"}}]}"#,
+        )
+        .unwrap();
+        let location = find_location(&json).unwrap();
+        assert!((location.latitude - 52.52).abs() < 1e-9);
+
+        let json: serde_json::Value = serde_json::from_str(r#"{"format":{"tags":{}}}"#).unwrap();
+        assert!(find_location(&json).is_none());
+    }
+
+    #[test]
+    fn test_located_into_local() {
+        let utc = parse_video_datetime("2024-07-15T10:00:00Z").unwrap();
+        let berlin = GeoLocation::new(52.52, 13.405).unwrap();
+        let (local, offset) = ContainerTime::Located(utc, berlin)
+            .into_local(Path::new("clip.mp4"), ZoneRules::default());
+        assert_eq!(local.hour(), 12);
+        assert_eq!(offset.unwrap().local_minus_utc(), 2 * 3600);
+    }
+
+    #[test]
+    fn test_utc_into_configured_zone() {
+        let utc = parse_video_datetime("2024-07-15T10:00:00Z").unwrap();
+        let rules = ZoneRules {
+            home: Some(chrono_tz::America::New_York),
+            schedule: &[],
+        };
+        let (local, offset) = ContainerTime::Utc(utc).into_local(Path::new("clip.mp4"), rules);
+        assert_eq!(local.hour(), 6);
+        assert_eq!(offset.unwrap().local_minus_utc(), -4 * 3600);
+
+        // A local timestamp in the filename still takes precedence
+        let (local, _) =
+            ContainerTime::Utc(utc).into_local(Path::new("VID_20240715_190000.mp4"), rules);
+        assert_eq!(local.hour(), 19);
+    }
 }
@@ -0,0 +1,213 @@
+//! XMP sidecar discovery and time extraction
+//!
+//! Raw developers such as Lightroom and darktable keep edits and metadata in
+//! an `.xmp` file next to the original, named either after the full filename
+//! (`DSC_0001.ARW.xmp`) or after its stem (`DSC_0001.xmp`). The capture time
+//! is read from `exif:DateTimeOriginal`, then `photoshop:DateCreated`, in
+//! either attribute or element form.
+
+use crate::error::{Error, Result};
+use crate::time::TimePrecision;
+use crate::time::datetime::parse_date_prefix;
+use chrono::{DateTime, FixedOffset, NaiveDateTime};
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tracing::trace;
+
+/// Sidecar extensions, tried in order
+const SIDECAR_EXTENSIONS: &[&str] = &["xmp", "XMP"];
+
+/// XMP date properties, in priority order
+const DATE_PROPERTIES: &[&str] = &["exif:DateTimeOriginal", "photoshop:DateCreated"];
+
+/// XMP date formats with a time zone designator
+const ZONED_FORMATS: &[(&str, TimePrecision)] = &[
+    ("%Y-%m-%dT%H:%M:%S%.f%:z", TimePrecision::Second),
+    ("%Y-%m-%dT%H:%M%:z", TimePrecision::Minute),
+];
+
+/// XMP date formats without a time zone designator
+const LOCAL_FORMATS: &[(&str, TimePrecision)] = &[
+    ("%Y-%m-%dT%H:%M:%S%.f", TimePrecision::Second),
+    ("%Y-%m-%dT%H:%M", TimePrecision::Minute),
+];
+
+/// Date read from a sidecar: local time, UTC offset if recorded, precision
+type XmpTime = (NaiveDateTime, Option<FixedOffset>, TimePrecision);
+
+/// Property regex: attribute `name="value"` or element `<name>value<`
+static PROPERTY_REGEX: OnceLock<Regex> = OnceLock::new();
+
+fn get_property_regex() -> &'static Regex {
+    PROPERTY_REGEX.get_or_init(|| {
+        let names = DATE_PROPERTIES.join("|");
+        Regex::new(&format!(
+            r#"({names})(?:\s*=\s*["']([^"']*)["']|>\s*([^<]*?)\s*<)"#
+        ))
+        .unwrap()
+    })
+}
+
+/// Find the sidecars of a file, full-filename form first
+pub fn find_sidecars(path: &Path) -> Vec<PathBuf> {
+    let (Some(name), Some(stem)) = (
+        path.file_name().and_then(|n| n.to_str()),
+        path.file_stem().and_then(|s| s.to_str()),
+    ) else {
+        return Vec::new();
+    };
+
+    let mut bases = vec![name];
+    if stem != name {
+        bases.push(stem);
+    }
+    bases
+        .into_iter()
+        .filter_map(|base| {
+            SIDECAR_EXTENSIONS
+                .iter()
+                .map(|ext| path.with_file_name(format!("{base}.{ext}")))
+                .find(|sidecar| sidecar.is_file())
+        })
+        .collect()
+}
+
+/// Whether a sidecar is named after the stem of `path` (`DSC_0001.xmp`)
+///
+/// Stem sidecars may be shared by several files, such as a RAW+JPEG pair.
+pub fn is_stem_sidecar(path: &Path, sidecar: &Path) -> bool {
+    sidecar.file_stem() == path.file_stem()
+}
+
+/// Extract the capture time from the sidecar of a file
+///
+/// Returns the local time, its UTC offset when the sidecar records one, and
+/// how precise it is.
+pub fn extract_xmp_time(path: &Path) -> Result<XmpTime> {
+    for sidecar in find_sidecars(path) {
+        let xmp = fs::read_to_string(&sidecar)?;
+        if let Some(time) = parse_xmp_time(&xmp) {
+            trace!(?sidecar, datetime = %time.0, "Found XMP date");
+            return Ok(time);
+        }
+    }
+
+    Err(Error::TimestampParse {
+        source_info: path.display().to_string(),
+        message: "No date found in XMP sidecar".to_string(),
+    })
+}
+
+/// Find the highest priority date property in an XMP packet
+fn parse_xmp_time(xmp: &str) -> Option<XmpTime> {
+    let values: Vec<_> = get_property_regex()
+        .captures_iter(xmp)
+        .filter_map(|caps| {
+            let value = caps.get(2).or_else(|| caps.get(3))?.as_str();
+            Some((caps.get(1)?.as_str(), value))
+        })
+        .collect();
+
+    DATE_PROPERTIES.iter().find_map(|property| {
+        values
+            .iter()
+            .filter(|(name, _)| name == property)
+            .find_map(|(_, value)| parse_xmp_date(value))
+    })
+}
+
+/// Parse an XMP date, which may omit the time, seconds or time zone
+fn parse_xmp_date(value: &str) -> Option<XmpTime> {
+    let value = value.trim();
+    let zoned = match value.strip_suffix('Z') {
+        Some(utc) => format!("{utc}+00:00"),
+        None => value.to_string(),
+    };
+
+    for (format, precision) in ZONED_FORMATS {
+        if let Ok(dt) = DateTime::parse_from_str(&zoned, format) {
+            return Some((dt.naive_local(), Some(*dt.offset()), *precision));
+        }
+    }
+    for (format, precision) in LOCAL_FORMATS {
+        if let Ok(dt) = NaiveDateTime::parse_from_str(value, format) {
+            return Some((dt, None, *precision));
+        }
+    }
+
+    // Date only, possibly without day or month
+    let (date, rest) = parse_date_prefix(value, &["%Y-%m-%d", "%Y-%m", "%Y"])?;
+    if !rest.is_empty() {
+        return None;
+    }
+    let precision = if value.len() == "YYYY-MM-DD".len() {
+        TimePrecision::Day
+    } else {
+        TimePrecision::Guessed
+    };
+    Some((date.and_hms_opt(0, 0, 0)?, None, precision))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn test_parse_xmp_time() {
+        let attribute = r#"<rdf:Description rdf:about=""
+            photoshop:DateCreated="2011-07-24T09:00:00"
+            exif:DateTimeOriginal="2011-07-24T10:15:30.50+02:00"/>"#;
+        let (time, offset, precision) = parse_xmp_time(attribute).unwrap();
+        assert_eq!(precision, TimePrecision::Second);
+        assert_eq!(
+            time,
+            local("2011-07-24 10:15:30") + chrono::Duration::milliseconds(500)
+        );
+        assert_eq!(offset, FixedOffset::east_opt(2 * 3600));
+
+        let element = "<rdf:Description>\n\
+            <photoshop:DateCreated>2011-07-24</photoshop:DateCreated>\n\
+            </rdf:Description>";
+        assert_eq!(
+            parse_xmp_time(element),
+            Some((local("2011-07-24 00:00:00"), None, TimePrecision::Day))
+        );
+
+        assert_eq!(
+            parse_xmp_date("2011-07-24T10:15Z"),
+            Some((
+                local("2011-07-24 10:15:00"),
+                FixedOffset::east_opt(0),
+                TimePrecision::Minute
+            ))
+        );
+        assert_eq!(parse_xmp_time(r#"xmp:CreateDate="2011-07-24""#), None);
+        assert_eq!(
+            parse_xmp_date("2011-07"),
+            Some((local("2011-07-01 00:00:00"), None, TimePrecision::Guessed))
+        );
+        assert_eq!(parse_xmp_date("yesterday"), None);
+    }
+
+    #[test]
+    fn test_find_sidecars() {
+        let dir = tempfile::tempdir().unwrap();
+        let raw = dir.path().join("DSC_0001.ARW");
+        fs::write(&raw, b"raw").unwrap();
+        assert!(find_sidecars(&raw).is_empty());
+
+        fs::write(dir.path().join("DSC_0001.xmp"), b"").unwrap();
+        fs::write(dir.path().join("DSC_0001.ARW.xmp"), b"").unwrap();
+        let names: Vec<_> = find_sidecars(&raw)
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, ["DSC_0001.ARW.xmp", "DSC_0001.xmp"]);
+    }
+}
@@ -24,7 +24,19 @@ pub fn display_summary(stats: &ProcessingStats, results: &[FileResult], dry_run:
     println!("    ✓ {}: {}", t!("stat_processed"), processed);
     println!("    ○ {}: {}", t!("stat_skipped"), skipped);
     println!("    ◎ {}: {}", t!("stat_duplicates"), duplicates);
+    let near_duplicates = stats.near_duplicates.load(Ordering::Relaxed);
+    if near_duplicates > 0 {
+        println!("    ≈ {}: {}", t!("stat_near_duplicates"), near_duplicates);
+    }
     println!("    ✗ {}: {}", t!("stat_failed"), failed);
+    let clock_corrected = stats.clock_corrected.load(Ordering::Relaxed);
+    if clock_corrected > 0 {
+        println!("    ◷ {}: {}", t!("stat_clock_corrected"), clock_corrected);
+    }
+    let interpolated = stats.interpolated.load(Ordering::Relaxed);
+    if interpolated > 0 {
+        println!("    ◷ {}: {}", t!("stat_interpolated"), interpolated);
+    }
 
     let failed_files: Vec<_> = results
         .iter()
@@ -1,6 +1,6 @@
 //! 配置向导渲染
 
-use crate::config::ClassificationRule;
+use crate::config::{ClassificationRule, format_time_sources};
 use crate::tui::components::{render_hint, render_title_block, three_panel_layout, wrap_lines};
 use crate::tui::labels::{
     bool_label, classification_label, file_operation_label, month_format_label,
@@ -304,6 +304,13 @@ fn draw_summary_step(frame: &mut Frame, area: Rect, wizard: &ConfigWizardState)
         Cell::from(t!("summary_dry_run")),
         Cell::from(wrap_lines(bool_label(config.dry_run).as_ref(), value_width)),
     ]));
+    rows.push(Row::new(vec![
+        Cell::from(t!("summary_time_sources")),
+        Cell::from(wrap_lines(
+            &format_time_sources(&config.time_sources),
+            value_width,
+        )),
+    ]));
 
     let table = Table::new(rows, [Constraint::Length(15), Constraint::Fill(1)])
         .block(
@@ -64,6 +64,48 @@ pub fn draw(frame: &mut Frame, area: Rect, state: &mut AppState) {
         ]),
     ];
 
+    let near_duplicates = state
+        .summary_state
+        .stats
+        .near_duplicates
+        .load(std::sync::atomic::Ordering::Relaxed);
+    if near_duplicates > 0 {
+        stats_lines.push(Line::from(vec![
+            Span::from(t!("stat_near_duplicates")),
+            Span::raw(": "),
+            Span::from(format!("{}", near_duplicates))
+                .style(Style::new().fg(theme().accent).bg(theme().bg)),
+        ]));
+    }
+
+    let clock_corrected = state
+        .summary_state
+        .stats
+        .clock_corrected
+        .load(std::sync::atomic::Ordering::Relaxed);
+    if clock_corrected > 0 {
+        stats_lines.push(Line::from(vec![
+            Span::from(t!("stat_clock_corrected")),
+            Span::raw(": "),
+            Span::from(format!("{}", clock_corrected))
+                .style(Style::new().fg(theme().accent).bg(theme().bg)),
+        ]));
+    }
+
+    let interpolated = state
+        .summary_state
+        .stats
+        .interpolated
+        .load(std::sync::atomic::Ordering::Relaxed);
+    if interpolated > 0 {
+        stats_lines.push(Line::from(vec![
+            Span::from(t!("stat_interpolated")),
+            Span::raw(": "),
+            Span::from(format!("{}", interpolated))
+                .style(Style::new().fg(theme().accent).bg(theme().bg)),
+        ]));
+    }
+
     if state.summary_state.dry_run {
         stats_lines.push(Line::from(""));
         stats_lines.push(
@@ -2,6 +2,7 @@
 
 use crate::config::{
     ClassificationRule, Config, EnumOption, FileOperation, MonthFormat, ProcessingMode,
+    TimeSourceKind, format_time_sources, parse_time_sources,
 };
 use crate::tui::labels::{
     bool_label, classification_label, file_operation_label, month_format_label,
@@ -216,12 +217,14 @@ pub enum FormField {
     Deduplication,
     /// 试运行
     DryRun,
+    /// 时间来源顺序
+    TimeSources,
 }
 
 impl FormField {
     /// 字段数量
     pub fn count() -> usize {
-        11
+        12
     }
 
     /// 获取全部字段
@@ -238,6 +241,7 @@ impl FormField {
             FormField::FileOperation,
             FormField::Deduplication,
             FormField::DryRun,
+            FormField::TimeSources,
         ]
     }
 
@@ -249,6 +253,7 @@ impl FormField {
                 | FormField::InputDirs
                 | FormField::OutputDir
                 | FormField::ExcludeDirs
+                | FormField::TimeSources
         )
     }
 
@@ -271,6 +276,7 @@ impl FormField {
             FormField::FileOperation => rust_i18n::t!("field_file_operation").to_string(),
             FormField::Deduplication => rust_i18n::t!("field_deduplication").to_string(),
             FormField::DryRun => rust_i18n::t!("field_dry_run").to_string(),
+            FormField::TimeSources => rust_i18n::t!("field_time_sources").to_string(),
         }
     }
 
@@ -294,6 +300,7 @@ impl FormField {
             }
             FormField::Deduplication => bool_label(state.deduplicate.value()).to_string(),
             FormField::DryRun => bool_label(state.dry_run.value()).to_string(),
+            FormField::TimeSources => state.time_sources.clone(),
         }
     }
 
@@ -422,6 +429,10 @@ pub struct ConfigWizardState {
     pub dry_run: BoolSelection,
     /// 按类型分类
     pub classify_by_type: BoolSelection,
+    /// 时间来源顺序（逗号分隔）
+    pub time_sources: String,
+    /// 加载的原始配置（保留表单未覆盖的设置）
+    pub base_config: Config,
     /// 配置名称
     pub config_name: String,
     /// 可用配置列表
@@ -447,7 +458,10 @@ pub struct ConfigWizardState {
 impl ConfigWizardState {
     /// 创建向导状态
     pub fn new() -> Self {
-        Self::default()
+        Self {
+            time_sources: format_time_sources(TimeSourceKind::all()),
+            ..Self::default()
+        }
     }
 
     /// 是否处于创建配置流程
@@ -494,6 +508,8 @@ impl ConfigWizardState {
             .select_by_index(if config.dry_run { 1 } else { 0 });
         self.classify_by_type
             .select_by_index(if config.classify_by_type { 1 } else { 0 });
+        self.time_sources = format_time_sources(&config.time_sources);
+        self.base_config = config.clone();
         self.config_name = config_path
             .file_stem()
             .map(|os| os.to_string_lossy().to_string())
@@ -516,6 +532,11 @@ impl ConfigWizardState {
             .filter(|p| !p.as_os_str().is_empty())
             .collect();
 
+        let time_sources = parse_time_sources(&self.time_sources)
+            .ok()
+            .filter(|sources| !sources.is_empty())
+            .unwrap_or_else(|| TimeSourceKind::all().to_vec());
+
         Config {
             input_dirs,
             output_dir: PathBuf::from(&self.output_dir),
@@ -528,7 +549,8 @@ impl ConfigWizardState {
             deduplicate: self.deduplicate.value(),
             dry_run: self.dry_run.value(),
             verbose: false,
-            ..Default::default()
+            time_sources,
+            ..self.base_config.clone()
         }
     }
 
@@ -555,6 +577,14 @@ impl ConfigWizardState {
             errors.push(rust_i18n::t!("output_dir_empty_error").to_string());
         }
 
+        if let Err(name) = parse_time_sources(&self.time_sources) {
+            errors.push(format!(
+                "{} {}",
+                rust_i18n::t!("time_sources_invalid_error"),
+                name
+            ));
+        }
+
         if errors.is_empty() {
             Ok(())
         } else {
@@ -770,6 +800,7 @@ impl ConfigWizardState {
             Some(FormField::InputDirs) => self.input_dirs = value,
             Some(FormField::OutputDir) => self.output_dir = value,
             Some(FormField::ExcludeDirs) => self.exclude_dirs = value,
+            Some(FormField::TimeSources) => self.time_sources = value,
             _ => {}
         }
     }
@@ -784,6 +815,7 @@ impl ConfigWizardState {
             Some(FormField::InputDirs) => self.input_dirs.clone(),
             Some(FormField::OutputDir) => self.output_dir.clone(),
             Some(FormField::ExcludeDirs) => self.exclude_dirs.clone(),
+            Some(FormField::TimeSources) => self.time_sources.clone(),
             _ => return,
         };
         self.form_state.enter_input_mode(&value);
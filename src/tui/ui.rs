@@ -15,7 +15,8 @@ pub fn run_processing(
 ) -> std::io::Result<SummaryState> {
     let mut processor = match crate::process::Processor::new(config.clone()) {
         Ok(p) => p,
-        Err(_) => {
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to start processing");
             let stats = crate::process::ProcessingStats::new();
             return Ok(SummaryState::new(
                 stats,